@@ -0,0 +1,641 @@
+/*
+This file contains the typed abstract syntax tree that the later phases of the
+compiler work on, along with the code that builds it from the parse tree.
+
+The parse tree passes information around through string keyed properties, which
+is convenient while parsing, but easy to get wrong everywhere else. It also keeps
+every separator and the right recursive shape of the grammar, such as the
+Elif_Stmt nodes nested inside each other. Once parsing has succeeded,
+lower_program turns it into the much smaller tree below: operators become Unary
+and Binary nodes, if/elif/else chains become a list of branches, and punctuation
+is dropped. Every variable is resolved to the stack slot it lives in along the
+way, so code generation never has to look anything up by name.
+*/
+//Names and spans are kept for diagnostics and the IR generator even where the x86 backend has no use for them
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::parse_c::{Node, NodeType};
+use crate::symbol_table_c::{*};
+use crate::token_c::Span;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Primitive {
+    Int,
+    Bool,
+    Char,
+    Float
+}
+
+impl Primitive {
+    pub fn from_name(name : &String) -> Primitive {
+        return match name.as_str() {
+            "int" => Primitive::Int,
+            "bool" => Primitive::Bool,
+            "char" => Primitive::Char,
+            "float" => Primitive::Float,
+            _ => panic!("Unknown primitive {}", name)
+        };
+    }
+
+    //Number of bytes a value of the type takes up in memory, which is also its alignment
+    pub fn size(&self) -> u32 {
+        return match self {
+            Primitive::Int => 4,
+            Primitive::Bool => 1,
+            Primitive::Char => 1,
+            Primitive::Float => 4
+        };
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    And,
+    Or,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual
+}
+
+impl BinaryOp {
+    pub fn from_operator(operator : &String) -> BinaryOp {
+        return match operator.as_str() {
+            "+" => BinaryOp::Add,
+            "-" => BinaryOp::Sub,
+            "*" => BinaryOp::Mul,
+            "/" => BinaryOp::Div,
+            "%" => BinaryOp::Mod,
+            "&" => BinaryOp::BitAnd,
+            "|" => BinaryOp::BitOr,
+            "^" => BinaryOp::BitXor,
+            "<<" => BinaryOp::ShiftLeft,
+            ">>" => BinaryOp::ShiftRight,
+            "&&" => BinaryOp::And,
+            "||" => BinaryOp::Or,
+            "==" => BinaryOp::Equal,
+            "!=" => BinaryOp::NotEqual,
+            "<" => BinaryOp::Less,
+            "<=" => BinaryOp::LessEqual,
+            ">" => BinaryOp::Greater,
+            ">=" => BinaryOp::GreaterEqual,
+            _ => panic!("Unknown binary operator {}", operator)
+        };
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UnaryOp {
+    Not,
+    BitNot,
+    Negate,
+    Plus
+}
+
+impl UnaryOp {
+    pub fn from_operator(operator : &String) -> UnaryOp {
+        return match operator.as_str() {
+            "!" => UnaryOp::Not,
+            "~" => UnaryOp::BitNot,
+            "-" => UnaryOp::Negate,
+            "+" => UnaryOp::Plus,
+            _ => panic!("Unknown unary operator {}", operator)
+        };
+    }
+}
+
+//A variable together with the place on the stack frame it is stored at
+#[derive(Clone, Debug)]
+pub struct Variable {
+    //Only read by ir_gen_c, which isn't built into the compiler yet
+    #[allow(dead_code)]
+    pub name : String,
+    pub primitive : Primitive,
+    //Offset from rbp, negative for locals and positive for arguments
+    pub addr : i32
+}
+
+#[derive(Debug)]
+pub struct Expr {
+    pub kind : ExprKind,
+    pub span : Span
+}
+
+#[derive(Debug)]
+pub enum ExprKind {
+    Constant(i32),
+    Bool(bool),
+    Variable(Variable),
+    Call {
+        name : String,
+        args : Vec<Expr>,
+        //Types from the declaration of the function, which arguments and the result are converted to
+        params : Vec<Primitive>,
+        return_type : Primitive
+    },
+    Unary {
+        op : UnaryOp,
+        operand : Box<Expr>
+    },
+    //Stores value in target and evaluates to what was stored
+    Assign {
+        target : Variable,
+        value : Box<Expr>
+    },
+    //x++ or x--, which evaluates to target from before op was applied to it and 1
+    Postfix {
+        op : BinaryOp,
+        target : Variable
+    },
+    Binary {
+        op : BinaryOp,
+        lhs : Box<Expr>,
+        rhs : Box<Expr>
+    }
+}
+
+#[derive(Debug)]
+pub enum Stmt {
+    VarDecl {
+        var : Variable,
+        init : Option<Expr>
+    },
+    Assign {
+        target : Variable,
+        value : Expr
+    },
+    //An expression evaluated only for its side effects, such as a function call
+    Expr(Expr),
+    Return {
+        value : Expr,
+        //Return type of the enclosing function, which the value is converted to
+        primitive : Primitive
+    },
+    //The branches of an if/elif chain in order, followed by the else body if there is one
+    If {
+        branches : Vec<Branch>,
+        else_body : Option<Vec<Stmt>>
+    },
+    While {
+        cond : Expr,
+        body : Vec<Stmt>
+    },
+    //Runs the body once before cond is checked for the first time
+    DoWhile {
+        body : Vec<Stmt>,
+        cond : Expr
+    },
+    For {
+        init : Option<Box<Stmt>>,
+        //A missing condition means the loop only ends through a return or a break
+        cond : Option<Expr>,
+        step : Option<Box<Stmt>>,
+        body : Vec<Stmt>
+    },
+    //The cases in the order they were written, control falls through from each into the next
+    Switch {
+        value : Expr,
+        cases : Vec<Case>
+    },
+    //Leaves the innermost loop or switch
+    Break,
+    //Goes on with the next iteration of the innermost loop
+    Continue
+}
+
+#[derive(Debug)]
+pub struct Branch {
+    pub cond : Expr,
+    pub body : Vec<Stmt>
+}
+
+//A case label of a switch, or its default label when there is no value
+#[derive(Debug)]
+pub struct Case {
+    pub value : Option<i32>,
+    pub body : Vec<Stmt>
+}
+
+#[derive(Debug)]
+pub struct Function {
+    pub name : String,
+    //Only read by ir_gen_c, the code generator takes the types from the calls
+    #[allow(dead_code)]
+    pub return_type : Primitive,
+    pub params : Vec<Variable>,
+    pub body : Vec<Stmt>,
+    //Number of bytes below rbp the function's locals need, including parameters passed in registers.
+    //Scopes that are never live at the same time share their slots
+    pub local_size : u32
+}
+
+//A function declared without a body, which may be defined later or in another object file
+#[derive(Debug)]
+pub struct Prototype {
+    pub name : String,
+    //Only read by ir_gen_c, the code generator takes the types from the calls
+    #[allow(dead_code)]
+    pub return_type : Primitive,
+    #[allow(dead_code)]
+    pub params : Vec<Variable>
+}
+
+#[derive(Debug)]
+pub enum Decl {
+    Function(Function),
+    Prototype(Prototype)
+}
+
+#[derive(Debug)]
+pub struct Program {
+    pub decls : Vec<Decl>
+}
+
+impl Program {
+    //Whether the program contains a body for the function, rather than only prototypes
+    pub fn is_defined(&self, name : &String) -> bool {
+        return self.decls.iter().any(|decl| matches!(decl, Decl::Function(function) if &function.name == name));
+    }
+}
+
+struct Scope {
+    table : Rc<STNode>,
+    variables : HashMap<String, Variable>,
+    //Bytes below rbp in use once the locals declared in this scope so far are included
+    used : u32
+}
+
+/*
+Keeps track of the variables that are visible while walking the parse tree.
+Variables only become visible once their declaration has been walked, so a use
+before a shadowing declaration still refers to the outer variable.
+
+Stack slots are handed out here as well, each aligned to the size of its type. A
+nested scope starts right below what its parent has used so far, so its locals sit
+below everything visible in it, and once it is left the same bytes are free again
+for its siblings and for whatever the parent declares afterwards.
+*/
+struct Lowering {
+    scopes : Vec<Scope>,
+    //Deepest byte below rbp used by a local of the current function, including locals of nested scopes
+    local_size : u32,
+    //Return type of the function being lowered
+    return_type : Primitive
+}
+
+impl Lowering {
+    fn enter_scope(&mut self, node : &Node) {
+        let used : u32 = match self.scopes.last() {
+            Option::Some(parent) => parent.used,
+            Option::None => 0
+        };
+        self.scopes.push(Scope{table : node.scope.clone().expect("Scope was not recorded while parsing"), variables : HashMap::new(), used});
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /* 
+    Makes a variable declared in the innermost scope visible from here on. Arguments
+    the caller passed on the stack already have an address above the return address,
+    everything else gets the next slot of the frame.
+    */
+    fn declare(&mut self, name : &str) -> Variable {
+        let scope : &mut Scope = self.scopes.last_mut().unwrap();
+        let symbol : Symbol = scope.table.get_table().query(name).unwrap().clone();
+        let primitive : Primitive = Primitive::from_name(&symbol.primitive);
+        let mut addr : i32 = symbol.addr;
+        if addr <= 0 {
+            scope.used = (scope.used + primitive.size()).next_multiple_of(primitive.size());
+            addr = -(scope.used as i32);
+            self.local_size = self.local_size.max(scope.used);
+        }
+        let variable : Variable = Variable{name : name.to_string(), primitive, addr};
+        scope.variables.insert(name.to_string(), variable.clone());
+        return variable;
+    }
+
+    fn resolve(&self, name : &String) -> Variable {
+        for scope in self.scopes.iter().rev() {
+            if let Option::Some(variable) = scope.variables.get(name) {
+                return variable.clone();
+            }
+        }
+        panic!("Variable {} was used without being declared", name);
+    }
+
+    //Declaration of a function that is called from the innermost scope
+    fn function(&self, name : &str) -> Symbol {
+        return self.scopes.last().unwrap().table.scope_lookup(name).expect("Function was called without being declared");
+    }
+}
+
+pub fn lower_program(program_start : &Node) -> Program {
+    let mut lowering : Lowering = Lowering{scopes : Vec::new(), local_size : 0, return_type : Primitive::Int};
+    let mut decls : Vec<Decl> = Vec::new();
+
+    //Other_Decl nodes form a chain with a function declaration in front of the next link
+    let mut other_decl : &Node = &program_start.children[0];
+    while let Option::Some(last) = other_decl.children.last() {
+        for child in &other_decl.children {
+            if let NodeType::Func_Decl = child.node_type {
+                if child.properties.contains_key("prototype") {
+                    decls.push(Decl::Prototype(lower_prototype(&mut lowering, child)));
+                }
+                else {
+                    decls.push(Decl::Function(lower_function(&mut lowering, child)));
+                }
+            }
+        }
+        other_decl = last;
+    }
+
+    return Program{decls};
+}
+
+fn lower_prototype(lowering : &mut Lowering, func_decl : &Node) -> Prototype {
+    lowering.enter_scope(func_decl);
+    let params : Vec<Variable> = lower_params(lowering, &func_decl.children[3]);
+    lowering.exit_scope();
+
+    return Prototype {
+        name : func_decl.children[1].properties["value"].clone(),
+        return_type : Primitive::from_name(&func_decl.children[0].properties["value"]),
+        params
+    };
+}
+
+fn lower_function(lowering : &mut Lowering, func_decl : &Node) -> Function {
+    lowering.enter_scope(func_decl);
+    lowering.local_size = 0;
+    lowering.return_type = Primitive::from_name(&func_decl.children[0].properties["value"]);
+
+    let params : Vec<Variable> = lower_params(lowering, &func_decl.children[3]);
+    let body : Vec<Stmt> = lower_body(lowering, &func_decl.children[6]);
+    lowering.exit_scope();
+
+    return Function {
+        name : func_decl.children[1].properties["value"].clone(),
+        return_type : lowering.return_type,
+        params,
+        body,
+        local_size : lowering.local_size
+    };
+}
+
+//Declares every parameter in an Arguments chain in the function's scope
+fn lower_params(lowering : &mut Lowering, arguments_node : &Node) -> Vec<Variable> {
+    let mut params : Vec<Variable> = Vec::new();
+    let mut arguments : &Node = arguments_node;
+    while arguments.properties.contains_key("identifier") {
+        params.push(lowering.declare(&arguments.properties["identifier"]));
+        if arguments.children.len() < 4 {
+            break;
+        }
+        arguments = &arguments.children[3];
+    }
+    return params;
+}
+
+fn lower_body(lowering : &mut Lowering, body : &Node) -> Vec<Stmt> {
+    let mut stmts : Vec<Stmt> = Vec::new();
+    for statement in &body.children {
+        stmts.push(lower_stmt(lowering, &statement.children[0]));
+    }
+    return stmts;
+}
+
+fn lower_stmt(lowering : &mut Lowering, node : &Node) -> Stmt {
+    match node.node_type {
+        NodeType::Return_Stmt => {
+            return Stmt::Return{value : lower_expr(lowering, &node.children[1]), primitive : lowering.return_type};
+        }
+        NodeType::VarDecl => {
+            let child : &Node = &node.children[1];
+            if let NodeType::Assign_Expr = child.node_type {
+                let var : Variable = lowering.declare(&child.properties["identifier"]);
+                return Stmt::VarDecl{var, init : Option::Some(lower_expr(lowering, &child.children[2]))};
+            }
+            return Stmt::VarDecl{var : lowering.declare(&child.properties["value"]), init : Option::None};
+        }
+        NodeType::Assign_Expr => {
+            let (target, value) : (Variable, Expr) = lower_assignment(lowering, node);
+            return Stmt::Assign{target, value};
+        }
+        NodeType::Expression => {
+            //An assignment whose value is never used is an assignment statement
+            let expr : Expr = lower_expr(lowering, node);
+            return match expr.kind {
+                ExprKind::Assign{target, value} => Stmt::Assign{target, value : *value},
+                kind => Stmt::Expr(Expr{kind, span : expr.span})
+            };
+        }
+        NodeType::Func_Call => {
+            return Stmt::Expr(lower_expr(lowering, node));
+        }
+        NodeType::Jump_Stmt => {
+            if node.properties["keyword"] == "break" {
+                return Stmt::Break;
+            }
+            return Stmt::Continue;
+        }
+        NodeType::If_Stmt => {
+            let mut branches : Vec<Branch> = vec![lower_branch(lowering, node)];
+            let else_body : Option<Vec<Stmt>> = lower_else_chain(lowering, &node.children[5], &mut branches);
+
+            return Stmt::If{branches, else_body};
+        }
+        NodeType::While_Stmt => {
+            lowering.enter_scope(node);
+            let cond : Expr = lower_expr(lowering, &node.children[2]);
+            let body : Vec<Stmt> = lower_body(lowering, block_body(&node.children[4]));
+            lowering.exit_scope();
+
+            return Stmt::While{cond, body};
+        }
+        NodeType::Do_While_Stmt => {
+            lowering.enter_scope(node);
+            let body : Vec<Stmt> = lower_body(lowering, &node.children[2]);
+            lowering.exit_scope();
+            let cond : Expr = lower_expr(lowering, &node.children[6]);
+
+            return Stmt::DoWhile{body, cond};
+        }
+        NodeType::For_Stmt => {
+            lowering.enter_scope(node);
+            let init : Option<Box<Stmt>> = lower_optional_stmt(lowering, &node.children[2]);
+            let cond : Option<Expr> = node.children[4].children.first().map(|cond| lower_expr(lowering, cond));
+            let step : Option<Box<Stmt>> = lower_optional_stmt(lowering, &node.children[6]);
+            let body : Vec<Stmt> = lower_body(lowering, block_body(&node.children[8]));
+            lowering.exit_scope();
+
+            return Stmt::For{init, cond, step, body};
+        }
+        NodeType::Switch_Stmt => {
+            lowering.enter_scope(node);
+            let value : Expr = lower_expr(lowering, &node.children[2]);
+            //The labels sit between the braces
+            let cases : Vec<Case> = node.children[5..node.children.len() - 1].iter().map(|label| lower_case(lowering, label)).collect();
+            lowering.exit_scope();
+
+            return Stmt::Switch{value, cases};
+        }
+        _ => panic!("Unexpected node in statement position")
+    }
+}
+
+fn lower_optional_stmt(lowering : &mut Lowering, optional_expr : &Node) -> Option<Box<Stmt>> {
+    return optional_expr.children.first().map(|child| Box::new(lower_stmt(lowering, child)));
+}
+
+//Collects the elif branches and the else body that follow an if statement
+fn lower_else_chain(lowering : &mut Lowering, elif_stmt : &Node, branches : &mut Vec<Branch>) -> Option<Vec<Stmt>> {
+    let mut elif_stmt : &Node = elif_stmt;
+
+    while elif_stmt.children.len() > 1 {
+        branches.push(lower_branch(lowering, elif_stmt));
+        elif_stmt = &elif_stmt.children[5];
+    }
+
+    if elif_stmt.children.len() == 1 {
+        let else_stmt : &Node = &elif_stmt.children[0];
+        lowering.enter_scope(else_stmt);
+        let body : Vec<Stmt> = lower_body(lowering, block_body(&else_stmt.children[1]));
+        lowering.exit_scope();
+
+        return Option::Some(body);
+    }
+    return Option::None;
+}
+
+//Lowers the condition and body of an if or elif node, which share the same layout
+fn lower_branch(lowering : &mut Lowering, node : &Node) -> Branch {
+    lowering.enter_scope(node);
+    let cond : Expr = lower_expr(lowering, &node.children[2]);
+    let body : Vec<Stmt> = lower_body(lowering, block_body(&node.children[4]));
+    lowering.exit_scope();
+
+    return Branch{cond, body};
+}
+
+//The body of a block, which only has braces around it when more than a single statement was written
+fn block_body(block : &Node) -> &Node {
+    return block.children.iter().find(|child| matches!(child.node_type, NodeType::Body)).unwrap();
+}
+
+//A case value is converted to int, the type the switch compares its value as
+fn lower_case(lowering : &mut Lowering, label : &Node) -> Case {
+    //Only a `case` label has a value, it was checked while parsing
+    let value : Option<i32> = label.constant;
+    let body : Vec<Stmt> = lower_body(lowering, &label.children[label.children.len() - 1]);
+    return Case{value, body};
+}
+
+fn lower_expr(lowering : &mut Lowering, node : &Node) -> Expr {
+    let kind : ExprKind = match node.node_type {
+        //The expression node only wraps the operator tree below it
+        NodeType::Expression => return lower_expr(lowering, &node.children[0]),
+
+        NodeType::Binary_Expr => {
+            let lhs : Expr = lower_expr(lowering, &node.children[0]);
+            let rhs : Expr = lower_expr(lowering, &node.children[2]);
+            ExprKind::Binary{op : BinaryOp::from_operator(&node.properties["operator"]), lhs : Box::new(lhs), rhs : Box::new(rhs)}
+        }
+
+        NodeType::Assign_Expr => {
+            let (target, value) : (Variable, Expr) = lower_assignment(lowering, node);
+            ExprKind::Assign{target, value : Box::new(value)}
+        }
+
+        //++x and --x are the same as x += 1 and x -= 1
+        NodeType::Unary_Expr if is_increment(&node.properties["operator"]) => {
+            let target : Variable = lowering.resolve(&node.children[1].properties["value"]);
+            let lhs : Expr = Expr{kind : ExprKind::Variable(target.clone()), span : node.children[1].span};
+            let rhs : Expr = Expr{kind : ExprKind::Constant(1), span : node.children[0].span};
+            let value : ExprKind = ExprKind::Binary{op : increment_op(&node.properties["operator"]), lhs : Box::new(lhs), rhs : Box::new(rhs)};
+            ExprKind::Assign{target, value : Box::new(Expr{kind : value, span : node.span})}
+        }
+
+        NodeType::Postfix_Expr => {
+            let target : Variable = lowering.resolve(&node.children[0].properties["value"]);
+            ExprKind::Postfix{op : increment_op(&node.properties["operator"]), target}
+        }
+
+        NodeType::Unary_Expr => {
+            let operand : Expr = lower_expr(lowering, &node.children[1]);
+            ExprKind::Unary{op : UnaryOp::from_operator(&node.properties["operator"]), operand : Box::new(operand)}
+        }
+
+        NodeType::Func_Call => {
+            let mut args : Vec<Expr> = Vec::new();
+            let mut call_args : &Node = &node.children[2];
+            while !call_args.children.is_empty() {
+                args.push(lower_expr(lowering, &call_args.children[0]));
+                if call_args.children.len() < 3 {
+                    break;
+                }
+                call_args = &call_args.children[2];
+            }
+            let function : Symbol = lowering.function(&node.properties["identifier"]);
+            ExprKind::Call {
+                name : node.properties["identifier"].clone(),
+                args,
+                params : function.params.iter().map(Primitive::from_name).collect(),
+                return_type : Primitive::from_name(&function.primitive)
+            }
+        }
+
+        NodeType::Constant => ExprKind::Constant(node.constant.expect("Constant was not checked while parsing")),
+
+        NodeType::Keyword => ExprKind::Bool(node.properties["value"] == "true"),
+
+        NodeType::Identifier => ExprKind::Variable(lowering.resolve(&node.properties["value"])),
+
+        _ => panic!("Unexpected node in expression position")
+    };
+
+    return Expr{kind, span : node.span};
+}
+
+/* 
+A compound assignment such as x += e is lowered to x = x + e, so code generation
+only ever sees plain assignments.
+*/
+fn lower_assignment(lowering : &mut Lowering, node : &Node) -> (Variable, Expr) {
+    let target : Variable = lowering.resolve(&node.properties["identifier"]);
+    let value : Expr = lower_expr(lowering, &node.children[2]);
+
+    let operator : &String = &node.properties["operator"];
+    if operator == "=" {
+        return (target, value);
+    }
+    let op : BinaryOp = BinaryOp::from_operator(&operator.trim_end_matches('=').to_string());
+    let lhs : Expr = Expr{kind : ExprKind::Variable(target.clone()), span : node.children[0].span};
+    return (target, Expr{kind : ExprKind::Binary{op, lhs : Box::new(lhs), rhs : Box::new(value)}, span : node.span});
+}
+
+fn is_increment(operator : &String) -> bool {
+    return operator == "++" || operator == "--";
+}
+
+fn increment_op(operator : &String) -> BinaryOp {
+    if operator == "++" {
+        return BinaryOp::Add;
+    }
+    return BinaryOp::Sub;
+}
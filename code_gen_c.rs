@@ -1,753 +1,771 @@
 /* 
 This is the file that will be responsible for assembly code generation. For now
 it will combine both the intermediate and target code generation into one unit of
-logic
+logic. Each function is first generated over virtual registers, which a register
+allocator then maps to physical registers and stack slots.
 */
 
-use std::{fs, rc::Rc};
+use std::fs;
+use std::collections::{HashMap, HashSet};
 
-use crate::{parse_c::{ Node, NodeType}, token_c::is_identifier};
-use crate::symbol_table_c::{*};
+use crate::ast_c::{*};
+use crate::machine_c::{*};
+use crate::linear_scan_c::linear_scan;
+use crate::graph_coloring_c::graph_coloring;
+use crate::symbol_table_c::REGISTER_ARGUMENTS;
 
-static mut CURRENT_LABEL_INDEX : u32 = 0;
+/* 
+A switch dispatches through a jump table when it has at least this many cases and
+the table would need no more than JUMP_TABLE_MAX_SPREAD entries per case, the
+entries between the cases going to the default label. Otherwise it does a
+binary search over the case values.
+*/
+const JUMP_TABLE_MIN_CASES : usize = 4;
+const JUMP_TABLE_MAX_SPREAD : i64 = 3;
 
-pub fn generate_code(filename : &String, current_node : &mut Node, symbol_table : &Rc<STNode>) {
-    let mut program_string : String = "".to_string();
+//Registers the System V AMD64 calling convention passes the first integer arguments in
+const ARGUMENT_REGISTERS : [&str; REGISTER_ARGUMENTS] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
 
-    generate_start_stub(&mut program_string, symbol_table);
+//Register allocation strategies that can be picked on the command line
+#[derive(Clone, Copy, PartialEq)]
+pub enum Allocator {
+    LinearScan,
+    GraphColoring
+}
 
-    let mut register_manager = RegisterManager{register_list : Vec::new()};
-    register_manager.initialize();
+impl Allocator {
+    pub fn from_name(name : &str) -> Option<Allocator> {
+        return match name {
+            "linear-scan" => Option::Some(Allocator::LinearScan),
+            "graph-coloring" => Option::Some(Allocator::GraphColoring),
+            _ => Option::None
+        };
+    }
+}
 
-    generate(&mut program_string, current_node, symbol_table, &mut register_manager);
+pub fn generate_code(filename : &String, program : &Program, allocator : Allocator) {
+    let mut program_string : String = "".to_string();
 
-    fs::write(filename, program_string).expect("Unable to write to file");
+    generate_start_stub(&mut program_string, program);
 
-}
+    let register_manager : RegisterManager = RegisterManager::new();
+    let mut next_label : u32 = 0;
 
-fn generate_start_stub(program_string : &mut String, symbol_table : &Rc<STNode>) {
-    
-    for (identifier, symbol) in symbol_table.get_table().symbol_table.iter() {
-        if symbol.func {
-            program_string.push_str(format!("global {}\n", identifier).as_str());
+    for decl in &program.decls {
+        if let Decl::Function(function) = decl {
+            generate_function(&mut program_string, function, &register_manager, allocator, &mut next_label);
         }
     }
-}
 
-fn generate_expr(program_string : &mut String, current_node : &mut Node, symbol_table : &Rc<STNode>, register_manager : &mut RegisterManager) {
-    assert!(current_node.properties.contains_key("terminal"));
-    //Left operand is a constant
-    
-    //Move it into a register
-    let expr_node : &mut Node = &mut current_node.children[0];
-    generate(program_string, expr_node, symbol_table, register_manager);
-    let reg_name : String = expr_node.properties["register"].clone();
+    fs::write(filename, program_string).expect("Unable to write to file");
 
+}
 
-    let subexpr_node : &mut Node = &mut current_node.children[1];
+fn generate_start_stub(program_string : &mut String, program : &Program) {
 
-    subexpr_node.properties.insert("prev_register".to_string(), reg_name.clone());
-    generate(program_string, subexpr_node, symbol_table, register_manager);
-    let result_reg : String = 
-    if subexpr_node.properties.contains_key("register") {
-        subexpr_node.properties["register"].clone()
+    for decl in &program.decls {
+        match decl {
+            Decl::Function(function) => program_string.push_str(format!("global {}\n", function.name).as_str()),
+            //Functions that are only declared have to come from another object file
+            Decl::Prototype(prototype) => {
+                if !program.is_defined(&prototype.name) {
+                    program_string.push_str(format!("extern {}\n", prototype.name).as_str());
+                }
+            }
+        }
     }
-    else {
-        reg_name
-    };
-    current_node.properties.insert("register".to_string(), result_reg);
 }
 
-fn generate(program_string : &mut String, current_node : &mut Node, symbol_table : &Rc<STNode>, register_manager : &mut RegisterManager) {
-    match current_node.node_type {
-        NodeType::Func_Decl => {
-            let func_name_node : &Node = &current_node.children[1];
-            program_string.push_str(format!("{}:\n", func_name_node.properties["value"]).as_str());
-            program_string.push_str(format!("\tpush rbp\n\tmov rbp, rsp\n").as_str());
-
+//next_label is the first label number the function may use, and is moved past the ones it did use
+fn generate_function(program_string : &mut String, function : &Function, register_manager : &RegisterManager, allocator : Allocator, next_label : &mut u32) {
+    let mut machine : MachineFunction = MachineFunction::new(*next_label);
+    generate_body(&mut machine, &mut Vec::new(), &function.body);
+    generate_epilogue(&mut machine);
+    *next_label = machine.next_label;
+
+    //Spill slots come right after the locals, the rest of the frame depends on the allocation
+    let local_slots : u32 = function.local_size.div_ceil(8);
+    let allocation : Allocation = match allocator {
+        Allocator::LinearScan => linear_scan(&machine, register_manager, local_slots),
+        Allocator::GraphColoring => graph_coloring(&machine, register_manager, local_slots)
+    };
 
-            //Save arguments to function on stack here
-            // let num_args: u32 = current_node.properties["arguments"].parse::<u32>().unwrap();
-            // if num_args > 0 {
-            //     program_string.push_str(format!("\tsub rsp, {}\n", num_args * 8).as_str());
-            // }
-            
+    //Caller-saved registers that hold a value across each call, since the callee may overwrite them
+    let live_out : Vec<HashSet<u32>> = live_out(&machine);
+    let mut call_saves : HashMap<usize, Vec<String>> = HashMap::new();
+    for (index, instr) in machine.instrs.iter().enumerate() {
+        if instr.kind == InstrKind::Call {
+            let live_registers : Vec<Location> = live_out[index].iter().map(|vreg| allocation.locations[vreg].clone()).collect();
+            let saved : Vec<String> = register_manager.allocatable(false).into_iter()
+                .filter(|name| live_registers.contains(&Location::Register(name.clone())))
+                .collect();
+            call_saves.insert(index, saved);
+        }
+    }
 
-            //Allocate space for all local variables here
-            if current_node.properties["var_alloc"].parse::<u32>().unwrap() > 0 {
-                program_string.push_str(format!("\tsub rsp, {}\n", current_node.properties["var_alloc"].parse::<u32>().unwrap() * 8).as_str());
+    //Only the registers that are actually used have to be preserved
+    let used_registers : Vec<&Location> = allocation.locations.values().collect();
+    let callee_saved : Vec<String> = register_manager.allocatable(true).into_iter()
+        .filter(|name| used_registers.contains(&&Location::Register(name.clone())))
+        .collect();
+    let caller_saved : Vec<String> = register_manager.allocatable(false).into_iter()
+        .filter(|name| call_saves.values().any(|saved| saved.contains(name)))
+        .collect();
+    let frame : Frame = Frame::new(local_slots + allocation.spill_slots, &caller_saved, &callee_saved);
+
+    //The calls and returns were generated before the frame was known, so the saves and restores are added now
+    for (index, instr) in machine.instrs.iter_mut().enumerate() {
+        let mut text : String = "".to_string();
+        match instr.kind {
+            InstrKind::Call => {
+                for reg_name in &call_saves[&index] {
+                    text.push_str(format!("\tmov qword {}, {}\n", stack_location(frame.call_slots[reg_name]), reg_name).as_str());
+                }
+                text.push_str(instr.text.as_str());
+                for reg_name in &call_saves[&index] {
+                    text.push_str(format!("\tmov {}, {}\n", reg_name, stack_location(frame.call_slots[reg_name])).as_str());
+                }
             }
-            
-
-            //Saving all Callee saved registers upon entering a function.
-            // program_string.push_str("\tpush rbx\n");
-            // let mut index : u32 = 12;
-            // while index < 16 {
-            //     program_string.push_str(format!("\tpush r{}\n", index).as_str());
-            //     index += 1;
-            // }
-
-            //This line is responsible for using the correct child node for the code segment to have it's own scope
-            let current_symbol_table: &Rc<STNode> = &symbol_table.children.borrow()[*symbol_table.scope_index.borrow()];
-            generate_children(program_string, current_node, current_symbol_table, register_manager);
-            //This line updates which children nodes have been used for code generation
-            *symbol_table.scope_index.borrow_mut() += 1;
-            
-
-            //Restoring all Callee saved registers upon exiting a function.
-            // index -= 1;
-            // while index > 11 {
-            //     program_string.push_str(format!("\tpop r{}\n", index).as_str());
-            //     index -= 1;
-            // }
-            // program_string.push_str("\tpop rbx\n");
-
-            program_string.push_str(format!("\tmov rsp, rbp\n\tpop rbp\n\tret\n").as_str());
-            
-        }
-        NodeType::Func_Call => {
-            /* 
-            1.) Store first 6 args in corresponding registers
-            2.) Pass excess arguments or arguments that can't fit onto the stack.
-            3.) Save all Caller-saved registers
-            4.) callq .<FUNCTION_NAME>
-            5.) Set properties["register"] = rax
-            6.) Restore all Caller-saved registers
-            7.) Restore argument registers?
-             */
-
-            generate_children(program_string, current_node, symbol_table, register_manager);
-            program_string.push_str(format!("\tcall {}\n", current_node.properties["identifier"]).as_str());
-            current_node.properties.insert("register".to_string(), "rax".to_string());
-            let arg_num = current_node.properties["arguments"].parse::<i32>().unwrap();
-            program_string.push_str(format!("\tadd rsp, {}\n", (arg_num * 8 )).as_str());
-        }
-        NodeType::Call_Args => {
-            generate_children(program_string, current_node, symbol_table, register_manager);
-            if current_node.children.len() > 0 {
-                program_string.push_str(format!("\tpush {}\n", current_node.children[0].properties["register"]).as_str());
+            InstrKind::Return => {
+                for (reg_name, addr) in &frame.saved_registers {
+                    text.push_str(format!("\tmov {}, {}\n", reg_name, stack_location(*addr)).as_str());
+                }
+                text.push_str(instr.text.as_str());
             }
+            _ => continue
         }
-        NodeType::Assign_Expr => {
-            let arith_expr : &mut Node = &mut current_node.children[2];
-            generate(program_string, arith_expr, symbol_table, register_manager);
-            let reg_name  = arith_expr.properties["register"].clone();
-
-            let identifier : &mut Node = &mut current_node.children[0];
-
-            symbol_table.modify_register(&identifier.properties["value"], register_manager.register_index(&reg_name));
-            current_node.properties.insert("register".to_string(), reg_name.clone());
+        instr.text = text;
+    }
 
-            let offset : i32 = symbol_table.scope_lookup(&current_node.properties["identifier"]).unwrap().addr.clone() as i32;
-            
-            register_manager.register_free(register_manager.register_index(&reg_name) as u32);
+    program_string.push_str(format!("{}:\n", function.name).as_str());
+    program_string.push_str("\tpush rbp\n\tmov rbp, rsp\n");
 
-            let operator : String = if offset > 0{"+".to_string()} else {"".to_string()};
-            program_string.push_str(format!("\tmov qword [rbp{}{}], {}\n", operator, offset, reg_name).as_str());
+    //Allocate space for all local variables, the spill slots and the saved registers here
+    program_string.push_str(format!("\tsub rsp, {}\n", frame.size).as_str());
+    for (reg_name, addr) in &frame.saved_registers {
+        program_string.push_str(format!("\tmov qword {}, {}\n", stack_location(*addr), reg_name).as_str());
+    }
 
+    //Arguments passed in registers are copied into their stack slots before anything can overwrite them
+    for (index, param) in function.params.iter().enumerate() {
+        if index < ARGUMENT_REGISTERS.len() {
+            let reg_name : String = sub_register(ARGUMENT_REGISTERS[index], param.primitive.size());
+            program_string.push_str(format!("\tmov {} {}, {}\n", size_keyword(&param.primitive), stack_location(param.addr), reg_name).as_str());
         }
-        NodeType::Arith_Expr => {
-            generate_expr(program_string, current_node, symbol_table, register_manager);
-        }
-        NodeType::Arith_Subexpr => {
-            if current_node.properties.contains_key("operator") {
-                let operator : String = current_node.properties["operator"].clone();
-
-                /* Generate term node completely, so all multiplication, division, and parenthesis
-                are given priority before addition, subtraction */
-                let term_node : &mut Node = &mut current_node.children[1];
-                generate(program_string, term_node, symbol_table, register_manager);
-                //The register that stores the result from evaluating term is stored in the result property
-                let result_reg : String = term_node.properties["register"].clone();
+    }
 
-                program_string.push_str(format!("\t{} {}, {}\n", to_operator(operator), current_node.properties["prev_register"].clone(), result_reg).as_str());
+    program_string.push_str(rewrite(&machine, &allocation.locations, register_manager).as_str());
 
-                register_manager.register_free(register_manager.register_index(&result_reg) as u32);
+    if !machine.read_only.is_empty() {
+        program_string.push_str(format!("section .rodata\n{}section .text\n", machine.read_only).as_str());
+    }
+}
 
-                current_node.properties.insert("register".to_string(), current_node.properties["prev_register"].clone());
+//Callee-saved registers are restored in front of every return once the frame is known
+fn generate_epilogue(machine : &mut MachineFunction) {
+    machine.push(Instr::with_kind(InstrKind::Return, "\tmov rsp, rbp\n\tpop rbp\n\tret\n".to_string()));
+}
 
-                let subexpr_node : &mut Node = &mut current_node.children[2];
+//Labels a break or continue in the body of a loop jumps to. A switch only has somewhere to break to
+struct Loop {
+    continue_label : String,
+    break_label : String
+}
 
-                //Store the results in the next subexpr node, so that it can pick up from where this node left off if needed.
-                subexpr_node.properties.insert("prev_register".to_string(), current_node.properties["prev_register"].clone());
-                generate(program_string, subexpr_node, symbol_table, register_manager);
-            }
-            else if current_node.properties.contains_key("terminal") {
-                let term_node : &mut Node = &mut current_node.children[0];
-                generate(program_string, term_node, symbol_table, register_manager);
+fn generate_body(machine : &mut MachineFunction, loops : &mut Vec<Loop>, body : &Vec<Stmt>) {
+    for stmt in body {
+        generate_stmt(machine, loops, stmt);
+    }
+}
 
-                let result_reg : String = term_node.properties["register"].clone();
-                current_node.properties.insert("register".to_string(), result_reg.clone());
+//loops holds the loops around stmt, innermost last
+fn generate_stmt(machine : &mut MachineFunction, loops : &mut Vec<Loop>, stmt : &Stmt) {
+    match stmt {
+        Stmt::VarDecl{var, init, ..} => {
+            //Declarations without a value only reserve their stack slot, which the prologue already did
+            if let Option::Some(value) = init {
+                let result : u32 = generate_expr(machine, value);
+                generate_store(machine, var, result);
             }
         }
-        NodeType::Arith_Term => {
-           generate_expr(program_string, current_node, symbol_table, register_manager);    //Free allocated register
+        Stmt::Assign{target, value, ..} => {
+            let result : u32 = generate_expr(machine, value);
+            generate_store(machine, target, result);
         }
-        NodeType::Arith_Subterm => {
-            if current_node.properties.contains_key("operator") {
-                let operator : String = current_node.properties["operator"].clone();
-
-                /* Generate term node completely, so all multiplication, division, and parenthesis
-                are given priority before addition, subtraction */
-                let factor_node : &mut Node = &mut current_node.children[1];
-                generate(program_string, factor_node, symbol_table, register_manager);
-                //The register that stores the result from evaluating term is stored in the result property
-                let result_reg : String = factor_node.properties["register"].clone();
-
-                /* This operator will always be multiplication or division, so proper assembly needs to be added to 
-                facilitate these operations. */
-
-                if operator == "/".to_string() {
-                    program_string.push_str("\tmov rdx, 0\n");
-                }
-                program_string.push_str(format!("\tmov rax, {}\n", current_node.properties["prev_register"].clone()).as_str());
-                program_string.push_str(format!("\t{} {}\n", to_operator(operator), result_reg).as_str());
-                program_string.push_str(format!("\tmov {}, rax\n", current_node.properties["prev_register"].clone()).as_str());
-                
-                current_node.properties.insert("register".to_string(), current_node.properties["prev_register"].clone());
-                
-
-                register_manager.register_free(register_manager.register_index(&result_reg) as u32);
-
-                let subterm_node : &mut Node = &mut current_node.children[2];
-                //Store the results in the next subexpr node, so that it can pick up from where this node left off if needed.
-                subterm_node.properties.insert("prev_register".to_string(), current_node.properties["prev_register"].clone());
-                generate(program_string, subterm_node, symbol_table, register_manager);
-            }
-            else if current_node.properties.contains_key("terminal"){
-                let factor_node : &mut Node = &mut current_node.children[0];
-                generate(program_string, factor_node, symbol_table, register_manager);
+        Stmt::Expr(expr) => {
+            generate_expr(machine, expr);
+        }
+        Stmt::Return{value, primitive, ..} => {
+            let result : u32 = generate_expr(machine, value);
+            generate_conversion(machine, primitive, result);
+            machine.push(Instr::new(format!("\tmov eax, {}\n", vreg_dword(result)), vec![], vec![result]));
 
-                let result_reg : String = factor_node.properties["register"].clone();
-                current_node.properties.insert("register".to_string(), result_reg.clone());
-            }
-            
-        }
-        NodeType::Arith_Factor => {
-            let operand : String = current_node.properties["terminal"].clone();
-            if is_identifier(&operand) {
-                //We have different behavior according to whether or not this factor is a function
-                if symbol_table.scope_lookup(&operand).unwrap().func {
-                    generate_children(program_string, current_node, symbol_table, register_manager);
-                    current_node.properties.insert("register".to_string(), current_node.children[0].properties["register"].clone());
+            //Leave the function straight away, even when the return is nested inside a loop or branch
+            generate_epilogue(machine);
+        }
+        Stmt::If{branches, else_body, ..} => {
+            let end_label : String = label_name(machine.label());
+
+            let mut index : usize = 0;
+            while index < branches.len() {
+                let branch : &Branch = &branches[index];
+                //The last branch without an else body falls straight through to the end
+                let next_label : String =
+                if index + 1 < branches.len() || else_body.is_some() {
+                    label_name(machine.label())
                 }
                 else {
-                    let reg_index : u32 = register_manager.register_alloc(0).unwrap();
-                    let reg_name : String = register_manager.register_name(reg_index);
-
-                    let offset : i32 = symbol_table.scope_lookup(&current_node.properties["terminal"]).unwrap().addr.clone() as i32;
-                    let operator : String = if offset > 0{"+".to_string()} else {"".to_string()};
-                    program_string.push_str(format!("\tmov {}, [rbp{}{}]\n", reg_name,operator, offset).as_str());
-                    
-                    symbol_table.modify_register(&current_node.properties["terminal"], register_manager.register_index(&reg_name.clone()));
-                    current_node.properties.insert("register".to_string(), reg_name);
-                }
-                
-            }
-            else {
-                let reg_index : u32 = register_manager.register_alloc(0).unwrap();
-                let reg_name : String = register_manager.register_name(reg_index);
-                
-                //Move it into a register
-                program_string.push_str(format!("\tmov qword {}, {}\n", reg_name, operand).as_str());
+                    end_label.clone()
+                };
 
-                current_node.properties.insert("register".to_string(), reg_name);
-            }
-        }
-        NodeType::Or_Expr => {
-            generate_expr(program_string, current_node, symbol_table, register_manager);
-        }
-        NodeType::Or_Subexpr => {
-            if current_node.properties.contains_key("operator") {
-                let operator : String = current_node.properties["operator"].clone();
+                generate_condition_jump(machine, &branch.cond, &next_label);
 
-                let and_expr_node : &mut Node = &mut current_node.children[1];
-                generate(program_string, and_expr_node, symbol_table, register_manager);
-                let result_reg : String = and_expr_node.properties["register"].clone();
+                //Generate code for body and extra statement to allow jumping to end
+                generate_body(machine, loops, &branch.body);
 
-                and_or_generator(program_string, &operator, &current_node.properties["prev_register"], &result_reg);
+                generate_jump(machine, &end_label);
+                generate_label(machine, &next_label);
 
-                register_manager.register_free(register_manager.register_index(&result_reg) as u32);
+                index += 1;
+            }
 
-                
-                current_node.properties.insert("register".to_string(), current_node.properties["prev_register"].clone());
+            if let Option::Some(body) = else_body {
+                generate_body(machine, loops, body);
+            }
 
-                let or_subexpr_node : &mut Node = &mut current_node.children[2];
-                or_subexpr_node.properties.insert("prev_register".to_string(), current_node.properties["prev_register"].clone());
-                generate(program_string, or_subexpr_node, symbol_table, register_manager);
+            //Without an else body the last branch already placed the end label
+            if else_body.is_some() {
+                generate_label(machine, &end_label);
             }
         }
-        NodeType::And_Expr => {
-            generate_expr(program_string, current_node, symbol_table, register_manager);
-        }
-        NodeType::And_Subexpr => {
-            if current_node.properties.contains_key("operator") {
-                let operator : String = current_node.properties["operator"].clone();
-
-                let equality_expr_node : &mut Node = &mut current_node.children[1];
-                generate(program_string, equality_expr_node, symbol_table, register_manager);
-                let result_reg : String = equality_expr_node.properties["register"].clone();
+        Stmt::While{cond, body, ..} => {
+            let start_label : String = label_name(machine.label());
+            let done_label : String = label_name(machine.label());
 
-                and_or_generator(program_string, &operator, &current_node.properties["prev_register"], &result_reg);
+            generate_label(machine, &start_label);
 
-                register_manager.register_free(register_manager.register_index(&result_reg) as u32);
+            generate_condition_jump(machine, cond, &done_label);
 
-                
-                current_node.properties.insert("register".to_string(), current_node.properties["prev_register"].clone());
+            loops.push(Loop{continue_label : start_label.clone(), break_label : done_label.clone()});
+            generate_body(machine, loops, body);
+            loops.pop();
 
-                let and_subexpr_node : &mut Node = &mut current_node.children[2];
-                and_subexpr_node.properties.insert("prev_register".to_string(), current_node.properties["prev_register"].clone());
-                generate(program_string, and_subexpr_node, symbol_table, register_manager);
-            }
+            generate_jump(machine, &start_label);
+            generate_label(machine, &done_label);
         }
-        NodeType::Equality_Expr => {
-            generate_expr(program_string, current_node, symbol_table, register_manager);
+        Stmt::DoWhile{body, cond, ..} => {
+            let start_label : String = label_name(machine.label());
+            let cond_label : String = label_name(machine.label());
+            let done_label : String = label_name(machine.label());
+
+            generate_label(machine, &start_label);
+
+            loops.push(Loop{continue_label : cond_label.clone(), break_label : done_label.clone()});
+            generate_body(machine, loops, body);
+            loops.pop();
+
+            //Go back to the start for as long as the condition holds
+            generate_label(machine, &cond_label);
+            let result : u32 = generate_expr(machine, cond);
+            machine.push(Instr::new(format!("\tcmp {}, 0\n", vreg_dword(result)), vec![], vec![result]));
+            generate_branch(machine, "jne", &start_label);
+            generate_label(machine, &done_label);
         }
-        NodeType::Equality_Subexpr => {
-            if current_node.properties.contains_key("operator") {
-                let operator : String = current_node.properties["operator"].clone();
+        Stmt::For{init, cond, step, body, ..} => {
+            if let Option::Some(init) = init {
+                generate_stmt(machine, loops, init);
+            }
 
-                let relational_expr_node : &mut Node = &mut current_node.children[1];
-                generate(program_string, relational_expr_node, symbol_table, register_manager);
-                let result_reg : String = relational_expr_node.properties["register"].clone();
+            let start_label : String = label_name(machine.label());
+            let step_label : String = label_name(machine.label());
+            let done_label : String = label_name(machine.label());
 
-                equality_generator(program_string, &operator, &current_node.properties["prev_register"], &result_reg);
+            generate_label(machine, &start_label);
 
-                register_manager.register_free(register_manager.register_index(&result_reg) as u32);
+            if let Option::Some(cond) = cond {
+                generate_condition_jump(machine, cond, &done_label);
+            }
 
-                
-                current_node.properties.insert("register".to_string(), current_node.properties["prev_register"].clone());
+            //A continue still runs the step before the condition is checked again
+            loops.push(Loop{continue_label : step_label.clone(), break_label : done_label.clone()});
+            generate_body(machine, loops, body);
+            loops.pop();
 
-                let equality_subexpr_node : &mut Node = &mut current_node.children[2];
-                equality_subexpr_node.properties.insert("prev_register".to_string(), current_node.properties["prev_register"].clone());
-                generate(program_string, equality_subexpr_node, symbol_table, register_manager);
+            generate_label(machine, &step_label);
+            if let Option::Some(step) = step {
+                generate_stmt(machine, loops, step);
             }
+
+            generate_jump(machine, &start_label);
+            generate_label(machine, &done_label);
         }
-        NodeType::Not_Expr => {
-            let operand : String = current_node.properties["terminal"].clone();
-            if is_identifier(&operand) {
-
-                if symbol_table.scope_lookup(&operand).unwrap().func {
-                    generate_children(program_string, current_node, symbol_table, register_manager);
-                    //The last child is the one that will be the func_call node
-                    let reg_name : String = current_node.children[current_node.children.len() - 1].properties["register"].clone();
-                    if current_node.properties.contains_key("unary") {
-                        program_string.push_str(format!("\txor {}, 1\n", reg_name).as_str());
-                    }
-                    current_node.properties.insert("register".to_string(), reg_name);
+        Stmt::Switch{value, cases, ..} => {
+            let done_label : String = label_name(machine.label());
+            let case_labels : Vec<String> = cases.iter().map(|_| label_name(machine.label())).collect();
+
+            //Without a default label a value that matches no case skips the whole body
+            let mut default_label : String = done_label.clone();
+            let mut targets : Vec<(i32, String)> = Vec::new();
+            for (case, label) in cases.iter().zip(case_labels.iter()) {
+                match case.value {
+                    Option::Some(value) => targets.push((value, label.clone())),
+                    Option::None => default_label = label.clone()
                 }
-                else {
-                    let reg_index : u32 = register_manager.register_alloc(0).unwrap();
-                    let reg_name : String = register_manager.register_name(reg_index);
-
-                    let offset : i32 = symbol_table.scope_lookup(&current_node.properties["terminal"]).unwrap().addr.clone() as i32;
-                    let operator : String = if offset > 0{"+".to_string()} else {"".to_string()};
-                    program_string.push_str(format!("\tmov {}, [rbp{}{}]\n", reg_name, operator, offset).as_str());
-                    
-                    if current_node.properties.contains_key("unary") {
-                        program_string.push_str(format!("\txor {}, 1\n", reg_name).as_str());
-                    }
-
-                    symbol_table.modify_register(&current_node.properties["terminal"], register_manager.register_index(&reg_name.clone()));
-                    current_node.properties.insert("register".to_string(), reg_name);
-                }
-                
+            }
+            targets.sort_by_key(|(value, _)| *value);
+
+            let result : u32 = generate_expr(machine, value);
+            let spread : i64 = match (targets.first(), targets.last()) {
+                (Option::Some((min, _)), Option::Some((max, _))) => *max as i64 - *min as i64 + 1,
+                _ => 0
+            };
+            if targets.len() >= JUMP_TABLE_MIN_CASES && spread <= targets.len() as i64 * JUMP_TABLE_MAX_SPREAD {
+                generate_jump_table(machine, result, &targets, &default_label);
             }
             else {
-                //Left operand is a constant
-
-                //Allocate register for it
-                let reg_index : u32 = register_manager.register_alloc(0).unwrap();
-                let reg_name : String = register_manager.register_name(reg_index);
-                
-                //Move it into a register
-                program_string.push_str(format!("\tmov qword {}, {}\n", reg_name, operand).as_str());
-                if current_node.properties.contains_key("unary") {
-                    program_string.push_str(format!("\txor {}, 1\n", reg_name).as_str());
-                }
-                current_node.properties.insert("register".to_string(), reg_name);
+                generate_case_search(machine, result, &targets, &default_label);
+            }
+
+            //A continue inside the switch still belongs to the loop around it
+            let continue_label : String = loops.last().map(|outer| outer.continue_label.clone()).unwrap_or_default();
+            loops.push(Loop{continue_label, break_label : done_label.clone()});
+            for (case, label) in cases.iter().zip(case_labels.iter()) {
+                //Each case falls through into the next one unless its body jumps away
+                generate_label(machine, label);
+                generate_body(machine, loops, &case.body);
             }
+            loops.pop();
+
+            generate_label(machine, &done_label);
         }
-        NodeType::Relational_Expr => {
-            generate_expr(program_string, current_node, symbol_table, register_manager);
+        Stmt::Break => {
+            let label : String = loops.last().expect("break outside of a loop or switch").break_label.clone();
+            generate_jump(machine, &label);
         }
-        NodeType::Relational_Subexpr => {
-            if current_node.properties.contains_key("operator") {
-
-                let left_reg : String = current_node.properties["prev_register"].clone();
+        Stmt::Continue => {
+            let label : String = loops.last().expect("continue outside of a loop").continue_label.clone();
+            generate_jump(machine, &label);
+        }
+    }
+}
 
-                let unary_node : &mut Node = &mut current_node.children[1];
-                generate(program_string, unary_node, symbol_table, register_manager);
-                let right_reg : String = unary_node.properties["register"].clone();
+/* 
+Writes the value in result to the stack slot of the variable. It is converted to
+the variable's type first, so result holds exactly what was stored afterwards: a
+char wraps around the same way it does with gcc and a bool becomes 0 or 1.
+*/
+fn generate_store(machine : &mut MachineFunction, var : &Variable, result : u32) {
+    generate_conversion(machine, &var.primitive, result);
 
-                let relational_subexpr_node : &mut Node = &mut current_node.children[2];
-                generate(program_string, relational_subexpr_node, symbol_table, register_manager);
+    machine.push(Instr::new(format!("\tmov {} {}, {}\n", size_keyword(&var.primitive), stack_location(var.addr), sized_vreg(&var.primitive, result)), vec![], vec![result]));
+}
 
-                let operator : String = current_node.properties["operator"].clone();
+/* 
+Values are kept in the low 32 bits of their register, the way C promotes char
+and bool to int before doing arithmetic on them. Writing the 32 bit part of a
+register clears the rest, so the whole register holds the same value.
+*/
+fn extend(primitive : &Primitive, dst : String, src : String) -> String {
+    return match primitive {
+        //gcc treats a plain char as signed
+        Primitive::Char => format!("\tmovsx {}, {}\n", dst, src),
+        Primitive::Bool => format!("\tmovzx {}, {}\n", dst, src),
+        _ => format!("\tmov {}, {}\n", dst, src)
+    };
+}
 
-                let label_true : String = label_name(label_create());
-                let label_done : String = label_name(label_create());
+//Converts the value in vreg to primitive and back to 32 bits, so a char wraps around and a bool becomes 0 or 1
+fn generate_conversion(machine : &mut MachineFunction, primitive : &Primitive, vreg : u32) {
+    match primitive {
+        Primitive::Bool => {
+            let text : String = format!("\tcmp {}, 0\n\tsetne {}\n{}", vreg_dword(vreg), vreg_byte(vreg), extend(primitive, vreg_dword(vreg), vreg_byte(vreg)));
+            machine.push(Instr::new(text, vec![vreg], vec![vreg]));
+        }
+        Primitive::Char => {
+            machine.push(Instr::new(extend(primitive, vreg_dword(vreg), vreg_byte(vreg)), vec![vreg], vec![vreg]));
+        }
+        _ => {}
+    }
+}
 
-                //After doing comparison, the results will be stored in the register named in arith_node_left
-                
-                program_string.push_str(format!("\tcmp {}, {}\n", left_reg, right_reg).as_str());
-                program_string.push_str(format!("\t{} {}\n", jump_command(operator), label_true).as_str());
-                program_string.push_str(format!("\tmov {}, 0\n", left_reg).as_str());
-                program_string.push_str(format!("\tjmp {}\n", label_done).as_str());
-                program_string.push_str(format!("{}:\n", label_true).as_str());
-                program_string.push_str(format!("\tmov {}, 1\n", left_reg).as_str());
-                program_string.push_str(format!("{}:\n", label_done).as_str());
+//Part of the register a value of the type is stored from
+fn sized_vreg(primitive : &Primitive, vreg : u32) -> String {
+    if primitive.size() == 1 {
+        return vreg_byte(vreg);
+    }
+    return vreg_dword(vreg);
+}
 
-                current_node.properties.insert("register".to_string(), left_reg);
-            }
-            else if current_node.properties.contains_key("terminal"){
-                let unary_node : &mut Node = &mut current_node.children[0];
-                generate(program_string, unary_node, symbol_table, register_manager);
+//Size NASM needs for a memory operand holding a value of the type
+fn size_keyword(primitive : &Primitive) -> &'static str {
+    if primitive.size() == 1 {
+        return "byte";
+    }
+    return "dword";
+}
 
-                let result_reg : String = unary_node.properties["register"].clone();
-                current_node.properties.insert("register".to_string(), result_reg.clone());
-            }
-            
-        }       
-        NodeType::Condition_Expr => {
-            let expr_node : &mut Node = &mut current_node.children[0];
+//Jumps to false_label when cond evaluates to false
+fn generate_condition_jump(machine : &mut MachineFunction, cond : &Expr, false_label : &String) {
+    let result : u32 = generate_expr(machine, cond);
 
-            generate(program_string, expr_node, symbol_table, register_manager);
+    machine.push(Instr::new(format!("\tcmp {}, 0\n", vreg_dword(result)), vec![], vec![result]));
+    generate_branch(machine, "je", false_label);
+}
 
-            current_node.properties.insert("register".to_string(), expr_node.properties["register"].clone());
+//Generates code for an expression and returns the virtual register holding the result
+fn generate_expr(machine : &mut MachineFunction, expr : &Expr) -> u32 {
+    match &expr.kind {
+        ExprKind::Constant(value) => {
+            let result : u32 = machine.vreg();
 
+            machine.push(Instr::new(format!("\tmov {}, {}\n", vreg_dword(result), value), vec![result], vec![]));
+            return result;
         }
-        NodeType::Expression => {
-            let expr_node : &mut Node = &mut current_node.children[0];
-
-            generate(program_string, expr_node, symbol_table, register_manager);
-
-            current_node.properties.insert("register".to_string(), expr_node.properties["register"].clone());
+        ExprKind::Bool(value) => {
+            let result : u32 = machine.vreg();
 
+            machine.push(Instr::new(format!("\tmov {}, {}\n", vreg_dword(result), if *value {1} else {0}), vec![result], vec![]));
+            return result;
         }
-        NodeType::If_Stmt => {
-
-            let cond_expr : &mut Node = &mut current_node.children[2];
-
-            //This line is responsible for using the correct child node for the code segment to have it's own scope
-            let current_symbol_table: &Rc<STNode> = &symbol_table.children.borrow()[*symbol_table.scope_index.borrow()];
-            generate(program_string, cond_expr, current_symbol_table, register_manager);
-            let cond_reg : String = cond_expr.properties["register"].clone();
-
-            let end_label : String = label_name(label_create());
-            let mut next_label : String = end_label.clone();
-
-            let elif_stmt : &mut Node = &mut current_node.children[7];
+        ExprKind::Variable(var) => {
+            return generate_load(machine, var);
+        }
+        ExprKind::Assign{target, value} => {
+            let result : u32 = generate_expr(machine, value);
+            generate_store(machine, target, result);
+            return result;
+        }
+        ExprKind::Postfix{op, target} => {
+            //The old value is what the expression evaluates to, so the new one is computed in a copy
+            let result : u32 = generate_load(machine, target);
+            let updated : u32 = machine.vreg();
+            machine.push(Instr::copy(updated, result));
+            machine.push(Instr::new(format!("\t{} {}, 1\n", to_operator(op), vreg_dword(updated)), vec![updated], vec![updated]));
+            generate_store(machine, target, updated);
+            return result;
+        }
+        ExprKind::Call{name, args, params, return_type} => {
+            /* 
+            1.) Evaluate every argument before any of them is moved, since a nested call
+            would overwrite the argument registers
+            2.) Arguments past the sixth are pushed right to left, so the seventh ends up
+            just above the return address
+            3.) The first six are pushed as well, then popped into their registers
+            4.) Keep rsp 16 byte aligned at the call
+            5.) Values that stay live across the call in caller-saved registers are saved
+            and restored around it once registers have been allocated
+             */
 
-            if elif_stmt.children.len() > 0 {
-                next_label = label_name(label_create());
+            let mut arg_vregs : Vec<u32> = Vec::new();
+            for (arg, param) in args.iter().zip(params.iter()) {
+                let arg_vreg : u32 = generate_expr(machine, arg);
+                //The callee expects a char or bool argument to be extended to 32 bits already
+                generate_conversion(machine, param, arg_vreg);
+                arg_vregs.push(arg_vreg);
             }
-            
-
-            program_string.push_str(format!("\tcmp {}, 0\n", cond_reg).as_str());
-            program_string.push_str(format!("\tje {}\n", next_label).as_str());
-
-            //Generate code for body and extra statement to allow jumping to end
-            let body : &mut Node = &mut current_node.children[5];
-            generate(program_string, body, current_symbol_table, register_manager);
-            //This line updates which children nodes have been used for code generation
-            *symbol_table.scope_index.borrow_mut() += 1;
-
-            program_string.push_str(format!("\tjmp {}\n", end_label).as_str());
-            program_string.push_str(format!("{}:\n", next_label).as_str());
-            
-            let elif_stmt : &mut Node = &mut current_node.children[7];
-            //Pass end label to elif and else statements to make sure they can also jump to end
-            elif_stmt.properties.insert("end_label".to_string(), end_label.clone());
-            //Generate code for elif and else statements
-            generate(program_string, elif_stmt, symbol_table, register_manager);
-
-            //Generate the actual end label
-            if elif_stmt.children.len() > 0 {
-                program_string.push_str(format!("{}:\n", end_label).as_str());
-            }
-            
-        }
-        NodeType::Elif_Stmt => {
-            if current_node.children.len() == 1 {
-                //Then just pass through the generation to the else_stmt node
-                let else_stmt : &mut Node = &mut current_node.children[0];
-                else_stmt.properties.insert("end_label".to_string(), current_node.properties["end_label"].clone());
-
-                //This line is responsible for using the correct child node for the code segment to have it's own scope
-                let current_symbol_table: &Rc<STNode> = &symbol_table.children.borrow()[*symbol_table.scope_index.borrow()];
-                generate(program_string, else_stmt, current_symbol_table, register_manager);
-                *symbol_table.scope_index.borrow_mut() += 1;
-            }
-            else if current_node.children.len() > 1{
-                let cond_expr : &mut Node = &mut current_node.children[2];
 
-                //This line is responsible for using the correct child node for the code segment to have it's own scope
-                let current_symbol_table: &Rc<STNode> = &symbol_table.children.borrow()[*symbol_table.scope_index.borrow()];
-                generate(program_string, cond_expr, current_symbol_table, register_manager);
-                let cond_reg : String = cond_expr.properties["register"].clone();
+            //rsp is aligned after the prologue, so only an odd number of stack arguments needs padding
+            let stack_args : usize = args.len().saturating_sub(ARGUMENT_REGISTERS.len());
+            let padding : usize = (stack_args % 2) * 8;
+            if padding > 0 {
+                machine.push_text(format!("\tsub rsp, {}\n", padding));
+            }
 
-                let end_label : String = current_node.properties["end_label"].clone();
-                let mut next_label : String = end_label.clone();
+            for arg_vreg in arg_vregs.iter().rev() {
+                machine.push(Instr::new(format!("\tpush {}\n", vreg_name(*arg_vreg)), vec![], vec![*arg_vreg]));
+            }
 
-                let elif_stmt : &mut Node = &mut current_node.children[7];
+            //The argument registers are only written right before the call, so they can't hold anything else
+            let mut call_text : String = "".to_string();
+            for arg_register in ARGUMENT_REGISTERS.iter().take(args.len()) {
+                call_text.push_str(format!("\tpop {}\n", arg_register).as_str());
+            }
+            //Variadic C functions read the number of vector registers used from al
+            call_text.push_str("\tmov rax, 0\n");
+            call_text.push_str(format!("\tcall {}\n", name).as_str());
+            machine.push(Instr::with_kind(InstrKind::Call, call_text));
 
-                if elif_stmt.children.len() > 0 {
-                    next_label = label_name(label_create());
-                }
-                
+            if stack_args * 8 + padding > 0 {
+                machine.push_text(format!("\tadd rsp, {}\n", stack_args * 8 + padding));
+            }
 
-                program_string.push_str(format!("\tcmp {}, 0\n", cond_reg).as_str());
-                program_string.push_str(format!("\tje {}\n", next_label).as_str());
-                
-                //Generate code for body and extra statement to allow jumping to end
-                let body : &mut Node = &mut current_node.children[5];
-                generate(program_string, body, current_symbol_table, register_manager);
-                //This line updates which children nodes have been used for code generation
-                *symbol_table.scope_index.borrow_mut() += 1;
-
-                program_string.push_str(format!("\tjmp {}\n", end_label).as_str());
-                program_string.push_str(format!("{}:\n", next_label).as_str());
-                
-                let elif_stmt : &mut Node = &mut current_node.children[7];
-                //Pass end label to elif and else statements to make sure they can also jump to end
-                elif_stmt.properties.insert("end_label".to_string(), end_label.clone());
-                //Generate code for elif and else statements
-                generate(program_string, elif_stmt, symbol_table, register_manager);
+            //Move the result out of rax, since the next call or division would overwrite it
+            let result : u32 = machine.vreg();
+            let result_register : String = sub_register("rax", return_type.size());
+            machine.push(Instr::new(extend(return_type, vreg_dword(result), result_register), vec![result], vec![]));
+            return result;
+        }
+        ExprKind::Unary{op, operand} => {
+            let result : u32 = generate_expr(machine, operand);
+            match op {
+                //Any value other than 0 is true, so it is compared against 0 instead of flipping a bit
+                UnaryOp::Not => machine.push(Instr::new(format!("\tcmp {}, 0\n\tsete {}\n\tmovzx {}, {}\n", vreg_dword(result), vreg_byte(result), vreg_dword(result), vreg_byte(result)), vec![result], vec![result])),
+                UnaryOp::BitNot => machine.push(Instr::new(format!("\tnot {}\n", vreg_dword(result)), vec![result], vec![result])),
+                UnaryOp::Negate => machine.push(Instr::new(format!("\tneg {}\n", vreg_dword(result)), vec![result], vec![result])),
+                //The operand is already an int, so there is nothing left to promote
+                UnaryOp::Plus => {}
             }
+            return result;
         }
-        NodeType::While_Stmt => {
-            
-            let start_label : String = label_name(label_create());
-            let done_label : String = label_name(label_create());
-
-            program_string.push_str(format!("{}:\n", start_label).as_str());
-
-            let cond_expr : &mut Node = &mut current_node.children[2];
-
-            
-            //This line is responsible for using the correct child node for the code segment to have it's own scope
-            let current_symbol_table: &Rc<STNode> = &symbol_table.children.borrow()[*symbol_table.scope_index.borrow()];
-            generate(program_string, cond_expr, current_symbol_table, register_manager);
-            let cond_reg : String = cond_expr.properties["register"].clone();
-
-            program_string.push_str(format!("\tcmp {}, 0\n", cond_reg).as_str());
-            program_string.push_str(format!("\tje {}\n", done_label).as_str());
-
-            let body_node : &mut Node = &mut current_node.children[5];
-            generate(program_string, body_node, current_symbol_table, register_manager);
-            //This line updates which children nodes have been used for code generation
-            *symbol_table.scope_index.borrow_mut() += 1;
-
-            program_string.push_str(format!("\tjmp {}\n", start_label).as_str());
-            program_string.push_str(format!("{}:\n", done_label).as_str());
-
+        ExprKind::Binary{op : op @ (BinaryOp::And | BinaryOp::Or), lhs, rhs} => {
+            return generate_logical(machine, op, lhs, rhs);
         }
-        NodeType::For_Stmt => {
-
-
-            let optional_expr_1 : &mut Node = &mut current_node.children[2];
+        ExprKind::Binary{op, lhs, rhs} => {
+            let prev_vreg : u32 = generate_expr(machine, lhs);
+            let next_vreg : u32 = generate_expr(machine, rhs);
 
-            //This line is responsible for using the correct child node for the code segment to have it's own scope
-            let current_symbol_table: &Rc<STNode> = &symbol_table.children.borrow()[*symbol_table.scope_index.borrow()];
-            generate(program_string, optional_expr_1, current_symbol_table, register_manager);
-
-            let start_label : String = label_name(label_create());
-            let done_label : String = label_name(label_create());
-
-            program_string.push_str(format!("{}:\n", start_label).as_str());
-
-            let optional_expr_2 : &mut Node = &mut current_node.children[4];
-            generate(program_string, optional_expr_2, current_symbol_table, register_manager);
-            let cond_reg : String = optional_expr_2.children[0].properties["register"].clone();
+            return generate_binary(machine, op, prev_vreg, next_vreg);
+        }
+    }
+}
 
-            program_string.push_str(format!("\tcmp {}, 0\n", cond_reg).as_str());
-            program_string.push_str(format!("\tje {}\n", done_label).as_str());
+/* 
+Generates && and || as jumps, so the right operand is only evaluated when the left
+one doesn't decide the result already. Both operands are turned into 0 or 1 the
+same way a bool is, and the one evaluated last is the result.
+*/
+fn generate_logical(machine : &mut MachineFunction, operator : &BinaryOp, lhs : &Expr, rhs : &Expr) -> u32 {
+    let label_done : String = label_name(machine.label());
+
+    let result : u32 = generate_expr(machine, lhs);
+    generate_conversion(machine, &Primitive::Bool, result);
+    machine.push(Instr::new(format!("\tcmp {}, 0\n", vreg_dword(result)), vec![], vec![result]));
+    //A false left side decides &&, a true one decides ||
+    generate_branch(machine, if *operator == BinaryOp::And {"je"} else {"jne"}, &label_done);
+
+    let next_vreg : u32 = generate_expr(machine, rhs);
+    generate_conversion(machine, &Primitive::Bool, next_vreg);
+    machine.push(Instr::copy(result, next_vreg));
+    generate_label(machine, &label_done);
+    return result;
+}
 
-            let body_node : &mut Node = &mut current_node.children[9];
-            generate(program_string, body_node, current_symbol_table, register_manager);
+fn generate_load(machine : &mut MachineFunction, var : &Variable) -> u32 {
+    let result : u32 = machine.vreg();
 
-            let optional_expr_3 : &mut Node = &mut current_node.children[6];
-            generate(program_string, optional_expr_3, current_symbol_table, register_manager);
-            //This line updates which children nodes have been used for code generation
-            *symbol_table.scope_index.borrow_mut() += 1;
+    let operand : String = format!("{} {}", size_keyword(&var.primitive), stack_location(var.addr));
+    machine.push(Instr::new(extend(&var.primitive, vreg_dword(result), operand), vec![result], vec![]));
+    return result;
+}
 
-            program_string.push_str(format!("\tjmp {}\n", start_label).as_str());
-            program_string.push_str(format!("{}:\n", done_label).as_str());
+/* 
+Applies operator to both registers and returns the register holding the result.
+Addition, subtraction and the bitwise operators work on a copy of the left operand,
+which the register allocator can usually coalesce away. Every other operator overwrites prev_vreg.
+Operands are 32 bit ints, so results wrap around the same way they do with gcc.
+*/
+fn generate_binary(machine : &mut MachineFunction, operator : &BinaryOp, prev_vreg : u32, next_vreg : u32) -> u32 {
+    let prev_reg : String = vreg_dword(prev_vreg);
+    let next_reg : String = vreg_dword(next_vreg);
+
+    match operator {
+        BinaryOp::Add | BinaryOp::Sub | BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor => {
+            let result : u32 = machine.vreg();
+            machine.push(Instr::copy(result, prev_vreg));
+            machine.push(Instr::new(format!("\t{} {}, {}\n", to_operator(operator), vreg_dword(result), next_reg), vec![result], vec![result, next_vreg]));
+            return result;
         }
-        NodeType::Return_Stmt => {
-            generate_children(program_string, current_node, symbol_table, register_manager);
-            let ret_expr : &mut Node = &mut current_node.children[1];
-            if ret_expr.properties.contains_key("terminal") {
-                let operand : String = ret_expr.properties["terminal"].clone();
-                let source : String;
-                if is_identifier(&operand) {
-                    //Then we have an identifier
-                    let register_name : String = register_manager.register_name(symbol_table.scope_lookup(&operand).unwrap().register as u32);
-                    register_manager.register_free(register_manager.register_index(&register_name) as u32);
-                    source = register_name;
-                    
-                }
-                else {
-                    //Then we have an actual number
-                    source = operand;
-                }
-                program_string.push_str(format!("\tmov rax, {}\n", source).as_str());
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+            /* 
+            imul and idiv work on eax. idiv divides edx:eax, so the dividend is sign
+            extended into edx with cdq first. It truncates toward zero and leaves a
+            remainder with the sign of the dividend in edx, which is what C's / and %
+            do as well.
+            */
+            machine.push(Instr::new(format!("\tmov eax, {}\n", prev_reg), vec![], vec![prev_vreg]));
+            if *operator != BinaryOp::Mul {
+                machine.push_text("\tcdq\n".to_string());
             }
-            
-            
-        }        
-        _ => {
-            generate_children(program_string, current_node, symbol_table, register_manager);
+            machine.push(Instr::new(format!("\t{} {}\n", to_operator(operator), next_reg), vec![], vec![next_vreg]));
+            let result_register : &str = if *operator == BinaryOp::Mod {"edx"} else {"eax"};
+            machine.push(Instr::new(format!("\tmov {}, {}\n", prev_reg, result_register), vec![prev_vreg], vec![]));
+        }
+        BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+            /* 
+            The shift count has to be in cl, but rcx may be holding another value. It is
+            parked in rdx while the value is shifted in eax, which leaves the count's
+            register free to be anything, rcx included. Values are signed ints, so a
+            right shift is arithmetic.
+            */
+            machine.push(Instr::new(format!("\tmov eax, {}\n", prev_reg), vec![], vec![prev_vreg]));
+            machine.push(Instr::new(format!("\tmov edx, {}\n\txchg rdx, rcx\n\t{} eax, cl\n\tmov rcx, rdx\n", next_reg, to_operator(operator)), vec![], vec![next_vreg]));
+            machine.push(Instr::new(format!("\tmov {}, eax\n", prev_reg), vec![prev_vreg], vec![]));
+        }
+        BinaryOp::And | BinaryOp::Or => {
+            unreachable!("&& and || are generated as jumps by generate_logical");
+        }
+        BinaryOp::Equal | BinaryOp::NotEqual => {
+            equality_generator(machine, operator, prev_vreg, next_vreg);
+        }
+        BinaryOp::Less | BinaryOp::LessEqual | BinaryOp::Greater | BinaryOp::GreaterEqual => {
+            let label_true : String = label_name(machine.label());
+            let label_done : String = label_name(machine.label());
+
+            //After doing comparison, the results will be stored in prev_reg
+            machine.push(Instr::new(format!("\tcmp {}, {}\n", prev_reg, next_reg), vec![], vec![prev_vreg, next_vreg]));
+            generate_branch(machine, jump_command(operator).as_str(), &label_true);
+            machine.push(Instr::new(format!("\tmov {}, 0\n", prev_reg), vec![prev_vreg], vec![]));
+            generate_jump(machine, &label_done);
+            generate_label(machine, &label_true);
+            machine.push(Instr::new(format!("\tmov {}, 1\n", prev_reg), vec![prev_vreg], vec![]));
+            generate_label(machine, &label_done);
         }
-
     }
+    return prev_vreg;
 }
 
-fn generate_children(program_string : &mut String, current_node : &mut Node, symbol_table : &Rc<STNode>, register_manager : &mut RegisterManager) {
-    for mut node in &mut current_node.children {
-        generate(program_string, &mut node, symbol_table, register_manager);
+/* 
+Jumps to the case for the value in result through a table with an entry for every
+value from the smallest case to the largest. Entries hold the distance from the
+start of the table, so the code works wherever the program is loaded. Values
+outside the table wrap around to large unsigned numbers, which a single unsigned
+compare sends to the default label.
+*/
+fn generate_jump_table(machine : &mut MachineFunction, result : u32, targets : &[(i32, String)], default_label : &String) {
+    let min : i32 = targets[0].0;
+    let max : i32 = targets[targets.len() - 1].0;
+    let table_label : String = label_name(machine.label());
+
+    let index : u32 = machine.vreg();
+    machine.push(Instr::copy(index, result));
+    machine.push(Instr::new(format!("\tsub {}, {}\n", vreg_dword(index), min), vec![index], vec![index]));
+    machine.push(Instr::new(format!("\tcmp {}, {}\n", vreg_dword(index), max as i64 - min as i64), vec![], vec![index]));
+    generate_branch(machine, "ja", default_label);
+
+    let mut entries : Vec<String> = Vec::new();
+    for value in min..=max {
+        match targets.iter().find(|(case_value, _)| *case_value == value) {
+            Option::Some((_, label)) => entries.push(label.clone()),
+            Option::None => entries.push(default_label.clone())
+        }
     }
-}
 
-struct Register {
-    name : String,
-    in_use : bool,
-    addr : u32
-}
-struct RegisterManager {
-    register_list : Vec<Register>
+    //The 32 bit subtraction cleared the upper half of index, so it can be used as a 64 bit index
+    let mut jump_text : String = format!("\tlea rdx, [rel {}]\n", table_label);
+    jump_text.push_str(format!("\tmovsxd rax, dword [rdx+{}*4]\n", vreg_name(index)).as_str());
+    jump_text.push_str("\tadd rax, rdx\n\tjmp rax\n");
+    machine.push(Instr{kind : InstrKind::JumpTable(entries.clone()), text : jump_text, defs : vec![], uses : vec![index]});
+
+    machine.read_only.push_str(format!("{}:\n", table_label).as_str());
+    for entry in entries {
+        machine.read_only.push_str(format!("\tdd {} - {}\n", entry, table_label).as_str());
+    }
 }
 
-impl RegisterManager {
-
-    fn initialize(&mut self) {
-        // self.register_list = Vec::new();
-        //Hardcoded register names specifically for x86 architecture
-        self.register_list.push(Register{name : "rax".to_string(), in_use : false, addr : 0});
-        self.register_list.push(Register{name : "rbx".to_string(), in_use : false, addr : 0});
-        self.register_list.push(Register{name : "rcx".to_string(), in_use : false, addr : 0});
-        self.register_list.push(Register{name : "rdx".to_string(), in_use : false, addr : 0});
-        self.register_list.push(Register{name : "rsi".to_string(), in_use : false, addr : 0});
-        self.register_list.push(Register{name : "rdi".to_string(), in_use : false, addr : 0});
-
-        //Making sure that rsp, rbp cannot be overwritten by marking them in use
-        self.register_list.push(Register{name : "rbp".to_string(), in_use : true, addr : 0});
-        self.register_list.push(Register{name : "rsp".to_string(), in_use : true, addr : 0});
-
-        self.register_list.push(Register{name : "r8".to_string(), in_use : false, addr : 0});
-        self.register_list.push(Register{name : "r9".to_string(), in_use : false, addr : 0});
-        self.register_list.push(Register{name : "r10".to_string(), in_use : false, addr : 0});
-        self.register_list.push(Register{name : "r11".to_string(), in_use : false, addr : 0});
-        self.register_list.push(Register{name : "r12".to_string(), in_use : false, addr : 0});
-        self.register_list.push(Register{name : "r13".to_string(), in_use : false, addr : 0});
-        self.register_list.push(Register{name : "r14".to_string(), in_use : false, addr : 0});
-        self.register_list.push(Register{name : "r15".to_string(), in_use : false, addr : 0});
+//Compares result with the middle case value and goes on in whichever half could still match
+fn generate_case_search(machine : &mut MachineFunction, result : u32, targets : &[(i32, String)], default_label : &String) {
+    if targets.is_empty() {
+        generate_jump(machine, default_label);
+        return;
     }
 
-    fn register_alloc(&mut self, addr : u32) -> Option<u32> {
-        let mut index : usize = 0;
+    let middle : usize = targets.len() / 2;
+    let (value, label) : &(i32, String) = &targets[middle];
+    machine.push(Instr::new(format!("\tcmp {}, {}\n", vreg_dword(result), value), vec![], vec![result]));
+    generate_branch(machine, "je", label);
+    if targets.len() == 1 {
+        generate_jump(machine, default_label);
+        return;
+    }
 
-        while index < self.register_list.len() {
+    let less_label : String = label_name(machine.label());
+    generate_branch(machine, "jl", &less_label);
+    generate_case_search(machine, result, &targets[middle + 1..], default_label);
+    generate_label(machine, &less_label);
+    generate_case_search(machine, result, &targets[..middle], default_label);
+}
 
-            if !self.register_list[index].in_use {
-                self.register_list[index].in_use = true;
-                self.register_list[index].addr = addr;
-                return Option::Some(index as u32);            
-            }
+fn generate_label(machine : &mut MachineFunction, label : &String) {
+    machine.push(Instr::with_kind(InstrKind::Label(label.clone()), format!("{}:\n", label)));
+}
 
-            index += 1;
-        }
-        //Currently throw errors if there are no available registers.
-        return Option::None;
-    }
+fn generate_jump(machine : &mut MachineFunction, label : &String) {
+    machine.push(Instr::with_kind(InstrKind::Jump(label.clone()), format!("\tjmp {}\n", label)));
+}
 
-    fn register_free(&mut self, reg_index : u32) {
-        //Another check to make sure that rbp, rsp cannot be overwritten
-        if reg_index != 6 || reg_index != 7 {
-            self.register_list[reg_index as usize].in_use = false;
-            self.register_list[reg_index as usize].addr = 0;
-        }
-        
-    }
+//Conditional jump, which falls through to the next instruction when it isn't taken
+fn generate_branch(machine : &mut MachineFunction, jump : &str, label : &String) {
+    machine.push(Instr::with_kind(InstrKind::CondJump(label.clone()), format!("\t{} {}\n", jump, label)));
+}
+
+/* 
+Stack frame of a function, which is laid out once registers have been allocated.
+Locals take the first slots below rbp and spill slots follow them. Below those
+are the slots caller-saved registers are kept in while a call runs, and then the
+slots of the callee-saved registers the function writes. The size is kept a
+multiple of 16 so rsp stays aligned for calls made from the body.
+*/
+struct Frame {
+    saved_registers : Vec<(String, i32)>,
+    call_slots : HashMap<String, i32>,
+    size : u32
+}
 
-    fn register_index(&self, register_name : &String) -> i32 {
-        let name = register_name.as_str();
-        match name {
-            "rax" => 0,
-            "rbx" => 1,
-            "rcx" => 2,
-            "rdx" => 3,
-            "rsi" => 4,
-            "rdi" => 5,
-            "rbp" => 6,
-            "rsp" => 7,
-            "r8" => 8,
-            "r9" => 9,
-            "r10" => 10,
-            "r11" => 11,
-            "r12" => 12,
-            "r13" => 13,
-            "r14" => 14,
-            "r15" => 15,
-            _ => -1
+impl Frame {
+    fn new(used_slots : u32, caller_saved : &Vec<String>, callee_saved : &Vec<String>) -> Frame {
+        let mut slot : u32 = used_slots;
+
+        let mut call_slots : HashMap<String, i32> = HashMap::new();
+        for reg_name in caller_saved {
+            slot += 1;
+            call_slots.insert(reg_name.clone(), slot as i32 * -8);
         }
-    }
 
-    fn register_name(&self, reg_index : u32) -> String {
-        return self.register_list[reg_index as usize].name.clone();
+        let mut saved_registers : Vec<(String, i32)> = Vec::new();
+        for reg_name in callee_saved {
+            slot += 1;
+            saved_registers.push((reg_name.clone(), slot as i32 * -8));
+        }
+        return Frame{saved_registers, call_slots, size : (slot * 8).div_ceil(16) * 16};
     }
-
 }
 
-fn to_operator(operator : String) -> String {
-    let op_str = operator.as_str();
-    match op_str {
-        "+" => "add".to_string(),
-        "-" => "sub".to_string(),
-        "*" => "imul".to_string(),
-        "/" => "idiv".to_string(),
-        "&&" => "and".to_string(),
-        "||" => "or".to_string(),
-        "==" => "cmp".to_string(),
-        "!=" => "cmp".to_string(),
+fn to_operator(operator : &BinaryOp) -> String {
+    match operator {
+        BinaryOp::Add => "add".to_string(),
+        BinaryOp::Sub => "sub".to_string(),
+        BinaryOp::Mul => "imul".to_string(),
+        BinaryOp::Div => "idiv".to_string(),
+        BinaryOp::Mod => "idiv".to_string(),
+        BinaryOp::BitAnd => "and".to_string(),
+        BinaryOp::BitOr => "or".to_string(),
+        BinaryOp::BitXor => "xor".to_string(),
+        BinaryOp::ShiftLeft => "shl".to_string(),
+        BinaryOp::ShiftRight => "sar".to_string(),
+        BinaryOp::And => "and".to_string(),
+        BinaryOp::Or => "or".to_string(),
+        BinaryOp::Equal => "cmp".to_string(),
+        BinaryOp::NotEqual => "cmp".to_string(),
 
         _ => "Error: Incorrect operator found".to_string()
     }
 }
 
-fn jump_command(operator : String) -> String {
-    let op_str = operator.as_str();
-    match op_str {
-        "<" => "jl".to_string(),
-        "<=" => "jle".to_string(),
-        ">" => "jg".to_string(),
-        ">=" => "jge".to_string(),
+fn jump_command(operator : &BinaryOp) -> String {
+    match operator {
+        BinaryOp::Less => "jl".to_string(),
+        BinaryOp::LessEqual => "jle".to_string(),
+        BinaryOp::Greater => "jg".to_string(),
+        BinaryOp::GreaterEqual => "jge".to_string(),
 
         _ => "Error: Incorrect operator found".to_string()
     }
 }
 
 
-fn label_create() -> u32 {
-    unsafe {
-        CURRENT_LABEL_INDEX += 1;
-        return CURRENT_LABEL_INDEX;
-    }
-}
-
 fn label_name(index : u32)->String {
     return format!(".L{}", index);
 }
 
-fn and_or_generator(program_string : &mut String, operator : &String, prev_reg : &String, next_reg : &String) {
-    
-    let label_true: String = label_name(label_create());
-    let label_done : String = label_name(label_create());
+fn equality_generator(machine : &mut MachineFunction, operator : &BinaryOp, prev_vreg : u32, next_vreg : u32) {
+    let label_equal: String = label_name(machine.label());
+    let label_done : String = label_name(machine.label());
+    let prev_reg : String = vreg_dword(prev_vreg);
+    let next_reg : String = vreg_dword(next_vreg);
 
     let short_circuit_op : String;
     let full_eval_op : String;
-    if operator == "||" {
+    if *operator == BinaryOp::NotEqual {
         short_circuit_op = "1".to_string();
         full_eval_op = "0".to_string();
     }
@@ -755,38 +773,12 @@ fn and_or_generator(program_string : &mut String, operator : &String, prev_reg :
         short_circuit_op = "0".to_string();
         full_eval_op = "1".to_string();
     }
-    
-    program_string.push_str(format!("\tcmp {}, {}\n", prev_reg, short_circuit_op).as_str());    
-    program_string.push_str(format!("\tje {}\n", label_true.clone()).as_str());
-    program_string.push_str(format!("\tcmp {}, {}\n", next_reg, short_circuit_op).as_str());
-    program_string.push_str(format!("\tje {}\n", label_true.clone()).as_str());
-    program_string.push_str(format!("\tmov {}, {}\n", prev_reg, full_eval_op).as_str());
-    program_string.push_str(format!("\tjmp {}\n", label_done.clone()).as_str());
-    program_string.push_str(format!("{}:\n", label_true).as_str());
-    program_string.push_str(format!("\tmov {}, {}\n", prev_reg, short_circuit_op).as_str());
-    program_string.push_str(format!("{}:\n", label_done).as_str());
-}
-
-fn equality_generator(program_string : &mut String, operator : &String, prev_reg : &String, next_reg : &String) {
-    let label_equal: String = label_name(label_create());
-    let label_done : String = label_name(label_create());
 
-    let short_circuit_op : String;
-    let full_eval_op : String;
-    if operator == "!=" {
-        short_circuit_op = "1".to_string();
-        full_eval_op = "0".to_string();
-    }
-    else {
-        short_circuit_op = "0".to_string();
-        full_eval_op = "1".to_string();
-    }
-
-    program_string.push_str(format!("\tcmp {}, {}\n", prev_reg, next_reg).as_str());
-    program_string.push_str(format!("\tje {}\n", label_equal.clone()).as_str());
-    program_string.push_str(format!("\tmov {}, {}\n", prev_reg, short_circuit_op).as_str());
-    program_string.push_str(format!("\tjmp {}\n", label_done.clone()).as_str());
-    program_string.push_str(format!("{}:\n", label_equal).as_str());
-    program_string.push_str(format!("\tmov {}, {}\n", prev_reg, full_eval_op).as_str());
-    program_string.push_str(format!("{}:\n", label_done).as_str());
-}
\ No newline at end of file
+    machine.push(Instr::new(format!("\tcmp {}, {}\n", prev_reg, next_reg), vec![], vec![prev_vreg, next_vreg]));
+    generate_branch(machine, "je", &label_equal);
+    machine.push(Instr::new(format!("\tmov {}, {}\n", prev_reg, short_circuit_op), vec![prev_vreg], vec![]));
+    generate_jump(machine, &label_done);
+    generate_label(machine, &label_equal);
+    machine.push(Instr::new(format!("\tmov {}, {}\n", prev_reg, full_eval_op), vec![prev_vreg], vec![]));
+    generate_label(machine, &label_done);
+}
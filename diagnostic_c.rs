@@ -0,0 +1,160 @@
+/*
+This file is responsible for reporting problems found while compiling a source
+file. Every phase creates Diagnostic values instead of printing, and the driver
+renders them at the end in a format similar to rustc:
+
+    error[E0001]: expected `;`, found `}`
+     --> test.c:4:1
+      |
+    4 | }
+      | ^ expected `;`
+
+Error codes currently in use:
+    E0001 - a token that does not fit the grammar
+    E0002 - use of an identifier that has not been declared
+    E0003 - function called with the wrong number of arguments
+    E0004 - function declared again with a different signature
+    E0005 - function defined more than once
+    E0006 - division or remainder by a constant zero
+    E0007 - break outside of a loop or switch, or continue outside of a loop
+    E0008 - case or default label outside of a switch body
+    E0009 - case value or default label repeated in one switch
+    E0010 - integer constant that is not a decimal number
+    E0011 - function used where a value is expected
+    E0012 - type that the compiler does not support
+*/
+
+use crate::token_c::Span;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Error,
+    //Rendered like errors but doesn't stop the compile. No check reports one yet
+    #[allow(dead_code)]
+    Warning
+}
+
+impl Severity {
+    fn name(&self) -> &'static str {
+        return match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning"
+        };
+    }
+}
+
+//A span of source text together with a short message pointing at it
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub span : Span,
+    pub message : String
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity : Severity,
+    pub code : String,
+    pub message : String,
+    pub primary : Label,
+    pub secondary : Vec<Label>,
+    pub notes : Vec<String>
+}
+
+impl Diagnostic {
+    pub fn new(severity : Severity, code : &str, message : String, span : Span, label : String) -> Diagnostic {
+        return Diagnostic {
+            severity,
+            code : code.to_string(),
+            message,
+            primary : Label{span, message : label},
+            secondary : Vec::new(),
+            notes : Vec::new()
+        };
+    }
+
+    pub fn error(code : &str, message : String, span : Span, label : String) -> Diagnostic {
+        return Diagnostic::new(Severity::Error, code, message, span, label);
+    }
+
+    pub fn with_secondary(mut self, span : Span, message : String) -> Diagnostic {
+        self.secondary.push(Label{span, message});
+        return self;
+    }
+
+    pub fn with_note(mut self, note : String) -> Diagnostic {
+        self.notes.push(note);
+        return self;
+    }
+
+    pub fn is_error(&self) -> bool {
+        return self.severity == Severity::Error;
+    }
+}
+
+pub struct SourceFile {
+    pub name : String,
+    pub contents : String
+}
+
+//Maps the file ids stored in spans back to the files they came from
+pub struct SourceMap {
+    pub files : Vec<SourceFile>
+}
+
+impl SourceMap {
+    pub fn new() -> SourceMap {
+        return SourceMap{files : Vec::new()};
+    }
+
+    //Returns the file id that spans into this file should use
+    pub fn add_file(&mut self, name : String, contents : String) -> u32 {
+        self.files.push(SourceFile{name, contents});
+        return (self.files.len() - 1) as u32;
+    }
+
+    fn line_text(&self, span : &Span) -> &str {
+        return self.files[span.file_id as usize].contents.lines().nth(span.line as usize - 1).unwrap_or("");
+    }
+}
+
+//Renders a diagnostic with the source lines its labels point at
+pub fn render_diagnostic(diagnostic : &Diagnostic, source_map : &SourceMap) -> String {
+    let mut output : String = "".to_string();
+    let primary : &Span = &diagnostic.primary.span;
+
+    output.push_str(format!("{}[{}]: {}\n", diagnostic.severity.name(), diagnostic.code, diagnostic.message).as_str());
+
+    let mut labels : Vec<(&Label, char)> = vec![(&diagnostic.primary, '^')];
+    for label in &diagnostic.secondary {
+        labels.push((label, '-'));
+    }
+    labels.sort_by_key(|(label, _)| (label.span.file_id, label.span.line, label.span.column));
+
+    let gutter_width : usize = labels.iter().map(|(label, _)| label.span.line.to_string().len()).max().unwrap_or(1);
+    let gutter : String = " ".repeat(gutter_width);
+
+    output.push_str(format!("{}--> {}:{}:{}\n", gutter, source_map.files[primary.file_id as usize].name, primary.line, primary.column).as_str());
+    output.push_str(format!("{} |\n", gutter).as_str());
+
+    for (label, marker) in labels {
+        let text : &str = source_map.line_text(&label.span);
+        //Tabs are shown as single spaces so the markers line up with the text above them
+        let text : String = text.replace('\t', " ");
+
+        //Spans that run past the end of their first line are only underlined up to the end of that line
+        let start : usize = label.span.column as usize - 1;
+        let length : usize = (label.span.length as usize).min(text.len().saturating_sub(start)).max(1);
+
+        output.push_str(format!("{:>width$} | {}\n", label.span.line, text, width = gutter_width).as_str());
+        output.push_str(format!("{} | {}{} {}\n", gutter, " ".repeat(start), marker.to_string().repeat(length), label.message).as_str());
+    }
+
+    if !diagnostic.notes.is_empty() {
+        output.push_str(format!("{} |\n", gutter).as_str());
+    }
+    for note in &diagnostic.notes {
+        output.push_str(format!("{} = note: {}\n", gutter, note).as_str());
+    }
+
+    return output;
+}
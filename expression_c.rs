@@ -1,342 +1,339 @@
 /* This file will contain all necessary code to parse all types of expressions */
-use crate::{parse_c::{ create_node, get_current_token_index, parse, prev_token_index, Node, NodeType}, token_c::{is_identifier, is_operator, is_separator, Token}};
+use crate::{parse_c::{ create_node, create_expected_node, parse, try_parse, Node, NodeType, Parser}, token_c::{is_identifier, is_separator}};
 use crate::symbol_table_c::{*};
+use crate::diagnostic_c::Diagnostic;
+use crate::token_c::{Span, TokenType};
 use std::rc::Rc;
 
-fn parse_non_terminal_expr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>, expr_type : NodeType, subexpr_type : NodeType) -> bool {
-    let mut expr_node : Node = create_node(expr_type);
-    let mut subexpr_node : Node = create_node(subexpr_type);
-    
-    if
-    parse(&mut expr_node, tokens, symbol_table) &&
-    parse(&mut subexpr_node, tokens, symbol_table) {
-
-        current_node.children.push(expr_node);
-        current_node.children.push(subexpr_node);
-
-        if current_node.children[0].properties.contains_key("terminal") {
-            current_node.properties.insert("terminal".to_string(), current_node.children[0].properties["terminal"].clone());
-        }
-        return true;
-    }
-
-    return false;
-}
-
-fn semantic_check(tokens : &Vec<Token>, semantic_requirements : &Vec<String>) -> bool {
-    for requirement  in semantic_requirements {
-        if &tokens[get_current_token_index()].val == requirement {
+/* 
+Checks that an identifier names something with a value. Using an identifier that
+was never declared is reported as an error, and so is using a function anywhere
+but in a call, since it has no value of its own. Types are checked where they are
+written, so a variable of a type that can't be compiled was reported already.
+*/
+fn is_value(parser : &mut Parser, symbol_table : &Rc<STNode>, identifier : &String, span : Span, called : bool) -> bool {
+    match symbol_table.scope_lookup(identifier) {
+        Option::Some(symbol) => {
+            if symbol.func && !called {
+                parser.report(Diagnostic::error("E0011", format!("function `{}` used as a value", identifier), span, "only a call to a function has a value".to_string()));
+                return false;
+            }
             return true;
         }
+        Option::None => {
+            parser.report(Diagnostic::error("E0002", format!("cannot find `{}` in this scope", identifier), span, "not declared in this scope".to_string()));
+            return false;
+        }
     }
-    return false;
 }
 
-fn parse_non_terminal_subexpr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>, expr_type : NodeType, subexpr_type : NodeType, semantic_requirements : &Vec<String>) -> bool {
-    let mut operator_node : Node = create_node(NodeType::Operator);
-    let mut expr_node : Node = create_node(expr_type);
-    let mut subexpr_node : Node = create_node(subexpr_type);
-
-    if
-    semantic_check(tokens, semantic_requirements) &&
-    parse(&mut operator_node, tokens, symbol_table) &&
-    parse(&mut expr_node, tokens, symbol_table) &&
-    parse(&mut subexpr_node, tokens, symbol_table) {
-
-        current_node.children.push(operator_node);
-        current_node.children.push(expr_node);
-        current_node.children.push(subexpr_node);
-
-        current_node.properties.insert("operator".to_string(), current_node.children[0].properties["value"].clone());
-        return true;
-    }
-    else if parse(&mut expr_node, tokens, symbol_table) {
-
-        current_node.children.push(expr_node);
-        current_node.properties.insert("terminal".to_string(), current_node.children[0].properties["terminal"].clone());
-        return true;
-    }
-    else if is_separator(&tokens[get_current_token_index()].val) {
-        return true;
-    }
-    else if is_operator(&tokens[get_current_token_index()].val) {
-        return true;
-    }
-    return false;
+/* 
+Binding power of every binary operator, following C's precedence table. A higher
+number binds tighter, and all of these operators are left associative.
+*/
+fn binary_precedence(operator : &str) -> Option<u32> {
+    return match operator {
+        "||" => Option::Some(1),
+        "&&" => Option::Some(2),
+        "|" => Option::Some(3),
+        "^" => Option::Some(4),
+        "&" => Option::Some(5),
+        "==" | "!=" => Option::Some(6),
+        "<" | "<=" | ">" | ">=" => Option::Some(7),
+        "<<" | ">>" => Option::Some(8),
+        "+" | "-" => Option::Some(9),
+        "*" | "/" | "%" => Option::Some(10),
+        _ => Option::None
+    };
 }
 
-pub fn parse_arith_expr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-    return parse_non_terminal_expr(current_node, tokens, symbol_table, NodeType::Arith_Term, NodeType::Arith_Subexpr);
+pub fn is_assignment_operator(operator : &str) -> bool {
+    return matches!(operator, "=" | "+=" | "-=" | "*=" | "/=" | "%=" | "&=" | "|=" | "^=" | "<<=" | ">>=");
 }
 
-pub fn parse_arith_subexpr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-
-    let mut semantic_requirements : Vec<String> = Vec::new();
-    semantic_requirements.push("+".to_string());
-    semantic_requirements.push("-".to_string());
-
-    return parse_non_terminal_subexpr(current_node, tokens, symbol_table, NodeType::Arith_Term, NodeType::Arith_Subexpr, &semantic_requirements);
+fn is_increment_operator(operator : &String) -> bool {
+    return operator == "++" || operator == "--";
 }
 
-pub fn parse_arith_term(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-    return parse_non_terminal_expr(current_node, tokens, symbol_table, NodeType::Arith_Factor, NodeType::Arith_Subterm);
+fn is_unary_operator(operator : &String) -> bool {
+    return operator == "!" || operator == "~" || operator == "-" || operator == "+";
 }
 
-pub fn parse_arith_subterm(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-
-    let mut semantic_requirements : Vec<String> = Vec::new();
-    semantic_requirements.push("*".to_string());
-    semantic_requirements.push("/".to_string());
-
-    return parse_non_terminal_subexpr(current_node, tokens, symbol_table, NodeType::Arith_Factor, NodeType::Arith_Subterm, &semantic_requirements);
-}
-
-pub fn parse_arith_factor(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-    /* 
-    Production rules:
-    Arith_Factor -> constant | identifier | (expression)
-     */
-    let mut identifier_node : Node = create_node(NodeType::Identifier);
-    let mut constant_node : Node = create_node(NodeType::Constant);
-    let mut func_call_node : Node = create_node(NodeType::Func_Call);
-
-    if parse(&mut constant_node, tokens, symbol_table) {
-        current_node.properties.insert("terminal".to_string(), constant_node.properties["value"].clone());
-        current_node.children.push(constant_node);
-        return true;
-    }
-    else if is_identifier(&tokens[get_current_token_index()].val) {
-        if
-        tokens[get_current_token_index() + 1].val == "(" && 
-        parse(&mut func_call_node, tokens, symbol_table) &&
-        symbol_table.scope_lookup(&func_call_node.properties["identifier"]).unwrap().primitive == "int".to_string() {
-            current_node.properties.insert("terminal".to_string(), func_call_node.properties["identifier"].clone());
-            current_node.children.push(func_call_node);
-            return true;
-
+/* 
+Parses a run of binary operators with precedence climbing. Operands are parsed
+with parse_unary_expr, and an operator is only consumed here if it binds at least
+as tightly as min_precedence. Its right hand side may only contain operators that
+bind tighter than itself, which is what makes every operator left associative.
+*/
+fn parse_binary_expr(parser : &mut Parser, symbol_table : &Rc<STNode>, min_precedence : u32) -> Option<Node> {
+    let start_index : usize = parser.token_index();
+    let mut lhs : Node = parse_unary_expr(parser, symbol_table)?;
+
+    while let Option::Some(precedence) = binary_precedence(&parser.current_token().val) {
+        if precedence < min_precedence || parser.current_token().token_type != TokenType::Operator {
+            break;
         }
-        else if 
-        parse(&mut identifier_node, tokens, symbol_table) &&
-        (symbol_table.scope_lookup(&identifier_node.properties["value"]).unwrap().primitive == "int".to_string() ||
-        symbol_table.scope_lookup(&identifier_node.properties["value"]).unwrap().primitive == "bool".to_string()) {
-            current_node.properties.insert("terminal".to_string(), identifier_node.properties["value"].clone());
-            current_node.children.push(identifier_node);
-            return true;
+        let mut operator_node : Node = create_node(NodeType::Operator);
+        if !parse(&mut operator_node, parser, symbol_table) {
+            return Option::None;
         }
-        prev_token_index();
-        return false;
-
-        
+        let rhs : Node = parse_binary_expr(parser, symbol_table, precedence + 1)?;
+        check_division(parser, &operator_node.properties["value"], &rhs);
+
+        let mut binary_node : Node = create_node(NodeType::Binary_Expr);
+        binary_node.properties.insert("operator".to_string(), operator_node.properties["value"].clone());
+        binary_node.children.push(lhs);
+        binary_node.children.push(operator_node);
+        binary_node.children.push(rhs);
+        binary_node.span = parser.span_from(start_index);
+        lhs = binary_node;
     }
-    return false;
-}
-
-pub fn parse_or_epxr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-    return parse_non_terminal_expr(current_node, tokens, symbol_table, NodeType::And_Expr, NodeType::Or_Subexpr);
+    return Option::Some(lhs);
 }
 
-pub fn parse_or_subepxr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-
-    let mut semantic_requirements : Vec<String> = Vec::new();
-    semantic_requirements.push("||".to_string());
-
-    return parse_non_terminal_subexpr(current_node, tokens, symbol_table, NodeType::And_Expr, NodeType::Or_Subexpr, &semantic_requirements);
-}
-
-pub fn parse_and_expr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-    return parse_non_terminal_expr(current_node, tokens, symbol_table, NodeType::Equality_Expr, NodeType::And_Subexpr);
-}
-
-pub fn parse_and_subexpr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-
-    let mut semantic_requirements : Vec<String> = Vec::new();
-    semantic_requirements.push("&&".to_string());
-
-    return parse_non_terminal_subexpr(current_node, tokens, symbol_table, NodeType::Equality_Expr, NodeType::And_Subexpr, &semantic_requirements);
-
+//Reports a division or remainder whose divisor is the constant zero, which would trap at runtime
+fn check_division(parser : &mut Parser, operator : &str, rhs : &Node) {
+    let operator : &str = operator.trim_end_matches('=');
+    if (operator != "/" && operator != "%") || !matches!(rhs.node_type, NodeType::Constant) {
+        return;
+    }
+    if rhs.constant == Option::Some(0) {
+        let message : &str = if operator == "/" {"attempt to divide by zero"} else {"attempt to calculate the remainder with a divisor of zero"};
+        parser.report(Diagnostic::error("E0006", message.to_string(), rhs.span, "this divisor is zero".to_string()));
+    }
 }
 
-pub fn parse_equality_expr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-    return parse_non_terminal_expr(current_node, tokens, symbol_table, NodeType::Relational_Expr, NodeType::Equality_Subexpr);
+/* 
+Production rules:
+expr -> assign_expr | binary_expr
+Assignments bind looser than every other operator and group right to left, since
+the right hand side of one is parsed as a whole expression again.
+*/
+fn parse_assignment(parser : &mut Parser, symbol_table : &Rc<STNode>) -> Option<Node> {
+    if is_identifier(&parser.current_token().val) && is_assignment_operator(&parser.peek_token(1).val) {
+        let mut assign_expr : Node = create_node(NodeType::Assign_Expr);
+        if parse(&mut assign_expr, parser, symbol_table) {
+            return Option::Some(assign_expr);
+        }
+        return Option::None;
+    }
+    return parse_binary_expr(parser, symbol_table, 0);
 }
 
-pub fn parse_equality_subexpr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-
-    let mut semantic_requirements : Vec<String> = Vec::new();
-    semantic_requirements.push("==".to_string());
-    semantic_requirements.push("!=".to_string());
-
-    return parse_non_terminal_subexpr(current_node, tokens, symbol_table, NodeType::Relational_Expr, NodeType::Equality_Subexpr, &semantic_requirements);
+//Parses prefix operators, which bind tighter than any binary operator
+fn parse_unary_expr(parser : &mut Parser, symbol_table : &Rc<STNode>) -> Option<Node> {
+    //++ and -- write to their operand, so it has to be a variable
+    if is_increment_operator(&parser.current_token().val) {
+        let start_index : usize = parser.token_index();
+        let mut operator_node : Node = create_node(NodeType::Operator);
+        let mut identifier_node : Node = create_node(NodeType::Identifier);
+        if
+        !parse(&mut operator_node, parser, symbol_table) ||
+        !parse(&mut identifier_node, parser, symbol_table) ||
+        !is_value(parser, symbol_table, &identifier_node.properties["value"], identifier_node.span, false) {
+            return Option::None;
+        }
 
-}
+        let mut unary_node : Node = create_node(NodeType::Unary_Expr);
+        unary_node.properties.insert("operator".to_string(), operator_node.properties["value"].clone());
+        unary_node.children.push(operator_node);
+        unary_node.children.push(identifier_node);
+        unary_node.span = parser.span_from(start_index);
+        return Option::Some(unary_node);
+    }
+    if is_unary_operator(&parser.current_token().val) {
+        let start_index : usize = parser.token_index();
+        let mut operator_node : Node = create_node(NodeType::Operator);
+        if !parse(&mut operator_node, parser, symbol_table) {
+            return Option::None;
+        }
+        let mut operand : Node = parse_unary_expr(parser, symbol_table)?;
+
+        //A minus in front of a constant is part of the constant, so negative constants work wherever constants do
+        if operator_node.properties["value"] == "-" && matches!(operand.node_type, NodeType::Constant) {
+            let value : String = operand.properties["value"].clone();
+            let negated : String = match value.strip_prefix('-') {
+                Option::Some(positive) => positive.to_string(),
+                Option::None => format!("-{}", value)
+            };
+            operand.properties.insert("value".to_string(), negated);
+            operand.constant = operand.constant.map(|value| value.wrapping_neg());
+            operand.span = parser.span_from(start_index);
+            return Option::Some(operand);
+        }
 
-pub fn parse_relational_expr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {   
-    return parse_non_terminal_expr(current_node, tokens, symbol_table, NodeType::Not_Expr, NodeType::Relational_Subexpr);
+        let mut unary_node : Node = create_node(NodeType::Unary_Expr);
+        unary_node.properties.insert("operator".to_string(), operator_node.properties["value"].clone());
+        unary_node.children.push(operator_node);
+        unary_node.children.push(operand);
+        unary_node.span = parser.span_from(start_index);
+        return Option::Some(unary_node);
+    }
+    return parse_primary_expr(parser, symbol_table);
 }
 
-pub fn parse_relational_subexpr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-
-    let mut semantic_requirements : Vec<String> = Vec::new();
-    semantic_requirements.push("<".to_string());
-    semantic_requirements.push(">".to_string());
-    semantic_requirements.push("<=".to_string());
-    semantic_requirements.push(">=".to_string());
-
-    return parse_non_terminal_subexpr(current_node, tokens, symbol_table, NodeType::Not_Expr, NodeType::Relational_Subexpr, &semantic_requirements);
+/* 
+The value of a decimal constant as an int. Digits past what an int can hold wrap
+around the same way arithmetic on ints does, so only text that isn't a decimal
+number at all is rejected.
+*/
+fn constant_value(text : &str) -> Option<i32> {
+    if text.is_empty() || !text.chars().all(|c| c.is_ascii_digit()) {
+        return Option::None;
+    }
+    let mut value : i32 = 0;
+    for digit in text.bytes() {
+        value = value.wrapping_mul(10).wrapping_add((digit - b'0') as i32);
+    }
+    return Option::Some(value);
 }
 
-pub fn parse_not_expr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-    let mut operator_node : Node = create_node(NodeType::Operator);
+/* 
+Production rules:
+primary_expr -> constant | true | false | identifier | identifier++ | identifier-- | func_call | (expr)
+*/
+fn parse_primary_expr(parser : &mut Parser, symbol_table : &Rc<STNode>) -> Option<Node> {
+    let mut constant_node : Node = create_node(NodeType::Constant);
     let mut keyword_node : Node = create_node(NodeType::Keyword);
     let mut identifier_node : Node = create_node(NodeType::Identifier);
     let mut func_call_node : Node = create_node(NodeType::Func_Call);
-    let mut constant_node : Node = create_node(NodeType::Constant);
 
-    if 
-    "!" == tokens[get_current_token_index()].val &&
-    parse(&mut operator_node, tokens, symbol_table) {
-
-        current_node.children.push(operator_node);
-
-        current_node.properties.insert("unary".to_string(), "!".to_string());
+    if parser.current_token().val == "(" {
+        return parse_paren_expr(parser, symbol_table);
     }
-    if 
-    parse(&mut keyword_node, tokens, symbol_table) {
-        //Push the node that allowed for the parse to be succesful.
-        current_node.children.push(
-            if identifier_node.properties.contains_key("value") {
-                identifier_node
-            }
-            else {
-                keyword_node
+    else if parser.current_token().token_type == TokenType::Constant {
+        if parse(&mut constant_node, parser, symbol_table) {
+            constant_node.constant = constant_value(&constant_node.properties["value"]);
+            if constant_node.constant.is_none() {
+                parser.report(Diagnostic::error("E0010", format!("invalid integer constant `{}`", constant_node.properties["value"]), constant_node.span, "not a decimal integer".to_string()));
+                return Option::None;
             }
-        );
-        let terminal : String;
-        if
-        current_node.children[current_node.children.len() - 1].properties["value"] == "false".to_string() {
-            terminal = "0".to_string();
+            return Option::Some(constant_node);
         }
-        else {
-            terminal = "1".to_string();
+    }
+    else if parser.current_token().val == "true" || parser.current_token().val == "false" {
+        if parse(&mut keyword_node, parser, symbol_table) {
+            return Option::Some(keyword_node);
         }
-        current_node.properties.insert("terminal".to_string(), terminal);
-        return true;
     }
-
-    else if
-    is_identifier(&tokens[get_current_token_index()].val) {
-        if
-        tokens[get_current_token_index() + 1].val == "(" && 
-        parse(&mut func_call_node, tokens, symbol_table) &&
-        (symbol_table.scope_lookup(&func_call_node.properties["identifier"]).unwrap().primitive == "bool".to_string() ||
-        symbol_table.scope_lookup(&func_call_node.properties["identifier"]).unwrap().primitive == "int".to_string()) {
-            current_node.properties.insert("terminal".to_string(), func_call_node.properties["identifier"].clone());
-            current_node.children.push(func_call_node);
-            return true;
-
+    else if is_identifier(&parser.current_token().val) {
+        if parser.peek_token(1).val == "(" {
+            if 
+            parse(&mut func_call_node, parser, symbol_table) &&
+            is_value(parser, symbol_table, &func_call_node.properties["identifier"], func_call_node.span, true) {
+                return Option::Some(func_call_node);
+            }
         }
         else if 
-        parse(&mut identifier_node, tokens, symbol_table) &&
-        (symbol_table.scope_lookup(&identifier_node.properties["value"]).unwrap().primitive == "bool".to_string() ||
-        symbol_table.scope_lookup(&identifier_node.properties["value"]).unwrap().primitive == "int".to_string()){
-            current_node.properties.insert("terminal".to_string(), identifier_node.properties["value"].clone());
-            current_node.children.push(identifier_node);
-            return true;
+        parse(&mut identifier_node, parser, symbol_table) &&
+        is_value(parser, symbol_table, &identifier_node.properties["value"], identifier_node.span, false) {
+            if is_increment_operator(&parser.current_token().val) {
+                return parse_postfix_expr(parser, symbol_table, identifier_node);
+            }
+            return Option::Some(identifier_node);
         }
-        prev_token_index();
-        return false;
     }
-    else if
-    parse(&mut constant_node, tokens, symbol_table) {
-        current_node.children.push(constant_node);
-        current_node.properties.insert("terminal".to_string(), current_node.children[current_node.children.len() - 1].properties["value"].clone());
-        
-        return true;
+    else {
+        parser.expect("an expression".to_string());
     }
-    return false;
+    return Option::None;
 }
 
-pub fn parse_cond_expr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-    let mut or_expr_node : Node = create_node(NodeType::Or_Expr);
-    let mut rel_expr_node : Node = create_node(NodeType::Relational_Expr);
-    if parse(&mut rel_expr_node, tokens, symbol_table) {
-        //We have a relational expression
-        current_node.children.push(rel_expr_node);
-        return true;
-    }
-    if parse(&mut or_expr_node, tokens, symbol_table) {
-        //We have a boolean expression
-        current_node.children.push(or_expr_node);
-        return true;
+//Wraps a variable that is followed by ++ or --, which bind tighter than any prefix operator
+fn parse_postfix_expr(parser : &mut Parser, symbol_table : &Rc<STNode>, identifier_node : Node) -> Option<Node> {
+    let mut operator_node : Node = create_node(NodeType::Operator);
+    if !parse(&mut operator_node, parser, symbol_table) {
+        return Option::None;
     }
 
-
-    return false;
+    let mut postfix_node : Node = create_node(NodeType::Postfix_Expr);
+    postfix_node.properties.insert("operator".to_string(), operator_node.properties["value"].clone());
+    postfix_node.span = identifier_node.span.to(&operator_node.span);
+    postfix_node.children.push(identifier_node);
+    postfix_node.children.push(operator_node);
+    return Option::Some(postfix_node);
 }
 
+/* 
+The shape of the tree already says which operators group together, so the
+parentheses themselves are dropped and only widen the span of what they enclose.
+*/
+fn parse_paren_expr(parser : &mut Parser, symbol_table : &Rc<STNode>) -> Option<Node> {
+    let start_index : usize = parser.token_index();
+    let mut open_node : Node = create_expected_node(NodeType::Separator, "(");
+    let mut close_node : Node = create_expected_node(NodeType::Separator, ")");
+
+    if !parse(&mut open_node, parser, symbol_table) {
+        return Option::None;
+    }
+    let mut inner : Node = parse_assignment(parser, symbol_table)?;
+    if !parse(&mut close_node, parser, symbol_table) {
+        return Option::None;
+    }
+    inner.span = parser.span_from(start_index);
+    return Option::Some(inner);
+}
 
-pub fn parse_optional_expr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
+pub fn parse_optional_expr(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
 
     let mut expr: Node = create_node(NodeType::Expression);
     let mut assign_expr : Node = create_node(NodeType::Assign_Expr);
     let mut var_decl : Node = create_node(NodeType::VarDecl);
 
-    if parse(&mut var_decl, tokens, symbol_table) {
+    if try_parse(&mut var_decl, parser, symbol_table) {
         current_node.children.push(var_decl);
         return true;
     }
-    else if parse(&mut assign_expr, tokens, symbol_table) {
+    else if try_parse(&mut assign_expr, parser, symbol_table) {
         current_node.children.push(assign_expr);
         return true;
     }
-    else if parse(&mut expr, tokens, symbol_table) {
+    else if try_parse(&mut expr, parser, symbol_table) {
         current_node.children.push(expr);
         return true;
     }
-    else if is_separator(&tokens[get_current_token_index()].val) {
+    else if is_separator(&parser.current_token().val) {
         return true;
     }
 
     return false;
 }
 
-pub fn parse_expr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-
-    let mut arith_expr : Node = create_node(NodeType::Arith_Expr);
-    let mut conditional_expr : Node = create_node(NodeType::Condition_Expr);
-    
-    if parse(&mut conditional_expr, tokens, symbol_table) {
-        current_node.children.push(conditional_expr);
-        return true;
-    }
-    else if parse(&mut arith_expr, tokens, symbol_table) {
-        current_node.children.push(arith_expr);
-        return true;
+pub fn parse_expr(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
+    match parse_assignment(parser, symbol_table) {
+        Option::Some(expr_node) => {
+            current_node.children.push(expr_node);
+            return true;
+        }
+        Option::None => {
+            return false;
+        }
     }
-    
-
-    return false;
 }
 
-pub fn parse_assign_expr(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-    let mut expr_node : Node = create_node(NodeType::Arith_Expr);
+pub fn parse_assign_expr(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
+    let mut expr_node : Node = create_node(NodeType::Expression);
     let mut identity_node : Node = create_node(NodeType::Identifier);
     let mut operator_node : Node = create_node(NodeType::Operator);
 
     
-    if parse(&mut identity_node, tokens, symbol_table) {
+    if parse(&mut identity_node, parser, symbol_table) {
 
-        if symbol_table.scope_lookup(&identity_node.properties["value"]).unwrap().primitive == "int".to_string() {
-            expr_node = create_node(NodeType::Arith_Expr);
-        }
-        else if symbol_table.scope_lookup(&identity_node.properties["value"]).unwrap().primitive == "bool".to_string() {
-            expr_node = create_node(NodeType::Or_Expr);
+        if !is_value(parser, symbol_table, &identity_node.properties["value"], identity_node.span, false) {
+            parser.prev_token_index();
+            return false;
         }
 
+        if !is_assignment_operator(&parser.current_token().val) {
+            parser.expect("`=`".to_string());
+        }
         if
-        tokens[get_current_token_index()].val == "=".to_string() &&
-        parse(&mut operator_node, tokens, symbol_table) &&
-        parse(&mut expr_node, tokens, symbol_table) {
+        is_assignment_operator(&parser.current_token().val) &&
+        parse(&mut operator_node, parser, symbol_table) &&
+        parse(&mut expr_node, parser, symbol_table) {
+            check_division(parser, &operator_node.properties["value"], &expr_node.children[0]);
+            current_node.properties.insert("operator".to_string(), operator_node.properties["value"].clone());
+
             current_node.children.push(identity_node);
             current_node.children.push(operator_node);
             current_node.children.push(expr_node);
@@ -346,30 +343,52 @@ pub fn parse_assign_expr(current_node : &mut Node, tokens : &Vec<Token>, symbol_
             return true;
         }
         else {
-            prev_token_index();
+            parser.prev_token_index();
             return false;
         }
     }
     return false;
 }
 
-pub fn parse_func_call(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
+pub fn parse_func_call(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
 
     let mut identifier_node : Node = create_node(NodeType::Identifier);
-    let mut open_paren_node : Node = create_node(NodeType::Separator);
+    let mut open_paren_node : Node = create_expected_node(NodeType::Separator, "(");
     let mut arguments_node : Node = create_node(NodeType::Call_Args);
-    let mut close_paren_node : Node = create_node(NodeType::Separator);
+    let mut close_paren_node : Node = create_expected_node(NodeType::Separator, ")");
 
-    if
-    parse(&mut identifier_node, tokens, symbol_table) &&
-    parse(&mut open_paren_node, tokens, symbol_table) &&
-    parse(&mut arguments_node, tokens, symbol_table) &&
-    parse(&mut close_paren_node, tokens, symbol_table) {
+    if !parse(&mut identifier_node, parser, symbol_table) {
+        return false;
+    }
 
-        let arg_num : u32 = arguments_node.properties["arguments"].parse::<u32>().unwrap();
-        if arg_num != symbol_table.scope_lookup(&identifier_node.properties["value"]).unwrap().args {
+    let function : Symbol = match symbol_table.scope_lookup(&identifier_node.properties["value"]) {
+        Option::Some(symbol) if symbol.func => symbol,
+        _ => {
+            parser.report(Diagnostic::error("E0002", format!("cannot find function `{}` in this scope", identifier_node.properties["value"]), identifier_node.span, "not declared in this scope".to_string()));
+            return false;
+        }
+    };
+
+    if
+    parse(&mut open_paren_node, parser, symbol_table) &&
+    parse(&mut arguments_node, parser, symbol_table) &&
+    parse(&mut close_paren_node, parser, symbol_table) {
+
+        let arg_num : u32 = match arguments_node.properties.get("arguments").and_then(|count| count.parse::<u32>().ok()) {
+            Option::Some(count) => count,
+            Option::None => return false
+        };
+        if arg_num != function.args {
             //Number of arguments passed to function call does not match function definition
-            println!("Number of arguments for {} function call does not match definition", &identifier_node.properties["value"]);
+            let call_span : Span = identifier_node.span.to(&close_paren_node.span);
+            parser.report(
+                Diagnostic::error(
+                    "E0003", 
+                    format!("function `{}` takes {} {} but {} {} supplied", identifier_node.properties["value"], function.args, plural(function.args, "argument"), arg_num, if arg_num == 1 {"argument was"} else {"arguments were"}), 
+                    call_span, 
+                    format!("expected {} {}", function.args, plural(function.args, "argument")))
+                .with_secondary(function.span, "function defined here".to_string())
+            );
             return false;
         }
         
@@ -389,27 +408,33 @@ pub fn parse_func_call(current_node : &mut Node, tokens : &Vec<Token>, symbol_ta
     return false;
 }
 
-pub fn parse_call_args(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
+pub fn parse_call_args(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
     let mut expr_node : Node = create_node(NodeType::Expression);
-    let mut separator_node : Node = create_node(NodeType::Separator);
+    let mut separator_node : Node = create_expected_node(NodeType::Separator, ",");
     let mut call_arg_node : Node = create_node(NodeType::Call_Args);
 
-    if tokens[get_current_token_index()].val == ")".to_string() {
+    if parser.current_token().val == ")" {
         current_node.properties.insert("arguments".to_string(), "0".to_string());
         return true;
     }
 
     if
-    parse(&mut expr_node, tokens, symbol_table) {
+    parse(&mut expr_node, parser, symbol_table) {
 
         current_node.children.push(expr_node);
-        if tokens[get_current_token_index()].val == ")".to_string() {
+        if parser.current_token().val == ")" {
             current_node.properties.insert("arguments".to_string(), "1".to_string());
             return true;
         }
-        if 
-        parse(&mut separator_node, tokens, symbol_table) &&
-        parse(&mut call_arg_node, tokens, symbol_table) {
+        if !parse(&mut separator_node, parser, symbol_table) {
+            return false;
+        }
+        //A `,` has to be followed by another argument
+        if parser.current_token().val == ")" {
+            parser.expect("an expression".to_string());
+            return false;
+        }
+        if parse(&mut call_arg_node, parser, symbol_table) {
 
             let arg_num: i32 = call_arg_node.properties["arguments"].clone().parse::<i32>().unwrap() + 1;
             current_node.properties.insert("arguments".to_string(), arg_num.to_string());
@@ -419,12 +444,14 @@ pub fn parse_call_args(current_node : &mut Node, tokens : &Vec<Token>, symbol_ta
 
             return true;
         }
-
-        
-        
-
-        return true;
+        return false;
     }
 
     return false;
-}
\ No newline at end of file
+}
+fn plural(count : u32, word : &str) -> String {
+    if count == 1 {
+        return word.to_string();
+    }
+    return format!("{}s", word);
+}
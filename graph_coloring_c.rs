@@ -0,0 +1,207 @@
+/* 
+Graph coloring register allocation in the style of Chaitin and Briggs. Virtual
+registers that are live at the same time interfere, copies between registers
+that don't interfere are coalesced, and the graph is simplified by removing nodes
+that are certain to get a color. When no such node is left a spill candidate is
+removed anyway, and it is only spilled if select really finds no color for it.
+*/
+
+use std::collections::{HashMap, HashSet};
+
+use crate::machine_c::{*};
+
+struct InterferenceGraph {
+    //Neighbours of every node that hasn't been coalesced into another one
+    adjacency : HashMap<u32, HashSet<u32>>,
+    //Coalesced nodes point at the node that took their place
+    alias : HashMap<u32, u32>,
+    //Nodes live across a call, which prefer callee-saved registers
+    crosses_call : HashSet<u32>,
+    //How often each node appears in the code, which is what spilling it costs
+    occurrences : HashMap<u32, u32>
+}
+
+impl InterferenceGraph {
+    fn build(function : &MachineFunction) -> InterferenceGraph {
+        let live_out : Vec<HashSet<u32>> = live_out(function);
+        let mut graph : InterferenceGraph = InterferenceGraph{adjacency : HashMap::new(), alias : HashMap::new(), crosses_call : HashSet::new(), occurrences : HashMap::new()};
+
+        for (index, instr) in function.instrs.iter().enumerate() {
+            for vreg in instr.vregs() {
+                graph.adjacency.entry(vreg).or_default();
+                *graph.occurrences.entry(vreg).or_insert(0) += 1;
+            }
+
+            //A register written here interferes with everything still live afterwards
+            for def in &instr.defs {
+                for live in &live_out[index] {
+                    //The source of a copy holds the same value, so the two may share a register
+                    if *live == *def || (instr.kind == InstrKind::Move && instr.uses.contains(live)) {
+                        continue;
+                    }
+                    graph.add_edge(*def, *live);
+                }
+            }
+
+            if instr.kind == InstrKind::Call {
+                graph.crosses_call.extend(live_out[index].iter());
+            }
+        }
+        return graph;
+    }
+
+    fn add_edge(&mut self, a : u32, b : u32) {
+        self.adjacency.entry(a).or_default().insert(b);
+        self.adjacency.entry(b).or_default().insert(a);
+    }
+
+    fn find(&self, node : u32) -> u32 {
+        let mut node : u32 = node;
+        while let Option::Some(next) = self.alias.get(&node) {
+            node = *next;
+        }
+        return node;
+    }
+
+    fn degree(&self, node : u32) -> usize {
+        return self.adjacency[&node].len();
+    }
+
+    /* 
+    Briggs' conservative test: the copy is only coalesced if the combined node
+    has fewer neighbours of significant degree than it has colors, so merging can
+    never turn a colorable graph into one that needs a spill.
+    */
+    fn coalesce(&mut self, function : &MachineFunction, registers : &Registers) {
+        let mut changed : bool = true;
+        while changed {
+            changed = false;
+            for instr in &function.instrs {
+                if instr.kind != InstrKind::Move {
+                    continue;
+                }
+                let keep : u32 = self.find(instr.defs[0]);
+                let merge : u32 = self.find(instr.uses[0]);
+                if keep == merge || self.adjacency[&keep].contains(&merge) {
+                    continue;
+                }
+
+                let crosses_call : bool = self.crosses_call.contains(&keep) || self.crosses_call.contains(&merge);
+                let colors : usize = registers.colors(crosses_call).len();
+                let neighbours : HashSet<u32> = self.adjacency[&keep].union(&self.adjacency[&merge]).cloned().collect();
+                let significant : usize = neighbours.iter()
+                    .filter(|neighbour| self.degree(**neighbour) >= registers.colors(self.crosses_call.contains(neighbour)).len())
+                    .count();
+                if significant >= colors {
+                    continue;
+                }
+
+                for neighbour in self.adjacency.remove(&merge).unwrap() {
+                    let edges : &mut HashSet<u32> = self.adjacency.get_mut(&neighbour).unwrap();
+                    edges.remove(&merge);
+                    edges.insert(keep);
+                    self.adjacency.get_mut(&keep).unwrap().insert(neighbour);
+                }
+                if crosses_call {
+                    self.crosses_call.insert(keep);
+                }
+                let merged_occurrences : u32 = self.occurrences[&merge];
+                *self.occurrences.get_mut(&keep).unwrap() += merged_occurrences;
+                self.alias.insert(merge, keep);
+                changed = true;
+            }
+        }
+    }
+}
+
+//Colors available to the nodes, split by whether they survive a call
+struct Registers {
+    caller_saved : Vec<String>,
+    callee_saved : Vec<String>
+}
+
+impl Registers {
+    /* 
+    Colors in the order they are tried. A caller-saved register has to be saved
+    around every call its node crosses, so those nodes try callee-saved ones first,
+    while the others leave the callee-saved registers for them.
+    */
+    fn colors(&self, crosses_call : bool) -> Vec<String> {
+        if crosses_call {
+            return self.callee_saved.iter().chain(self.caller_saved.iter()).cloned().collect();
+        }
+        return self.caller_saved.iter().chain(self.callee_saved.iter()).cloned().collect();
+    }
+}
+
+pub fn graph_coloring(function : &MachineFunction, register_manager : &RegisterManager, spill_base : u32) -> Allocation {
+    let registers : Registers = Registers{caller_saved : register_manager.allocatable(false), callee_saved : register_manager.allocatable(true)};
+
+    let mut graph : InterferenceGraph = InterferenceGraph::build(function);
+    graph.coalesce(function, &registers);
+
+    let mut nodes : Vec<u32> = graph.adjacency.keys().cloned().collect();
+    nodes.sort();
+
+    //Simplify: remove nodes from the graph until it is empty, remembering the order
+    let mut degrees : HashMap<u32, usize> = nodes.iter().map(|node| (*node, graph.degree(*node))).collect();
+    let mut remaining : Vec<u32> = nodes.clone();
+    let mut stack : Vec<u32> = Vec::new();
+    while !remaining.is_empty() {
+        let colorable : Option<usize> = remaining.iter()
+            .position(|node| degrees[node] < registers.colors(graph.crosses_call.contains(node)).len());
+
+        //Without an easy node, pick the one that is cheapest to spill for how much it frees up
+        let index : usize = match colorable {
+            Option::Some(index) => index,
+            Option::None => {
+                let mut best : usize = 0;
+                for index in 1..remaining.len() {
+                    if spill_cost(&graph, &degrees, remaining[index]) < spill_cost(&graph, &degrees, remaining[best]) {
+                        best = index;
+                    }
+                }
+                best
+            }
+        };
+
+        let node : u32 = remaining.remove(index);
+        for neighbour in &graph.adjacency[&node] {
+            if let Option::Some(degree) = degrees.get_mut(neighbour) {
+                *degree = degree.saturating_sub(1);
+            }
+        }
+        degrees.remove(&node);
+        stack.push(node);
+    }
+
+    //Select: put the nodes back in reverse order and give each a color its neighbours don't have
+    let mut colors : HashMap<u32, Location> = HashMap::new();
+    let mut allocation : Allocation = Allocation::new();
+    while let Option::Some(node) = stack.pop() {
+        let taken : HashSet<String> = graph.adjacency[&node].iter()
+            .filter_map(|neighbour| match colors.get(neighbour) {
+                Option::Some(Location::Register(name)) => Option::Some(name.clone()),
+                _ => Option::None
+            })
+            .collect();
+
+        let location : Location = match registers.colors(graph.crosses_call.contains(&node)).into_iter().find(|name| !taken.contains(name)) {
+            Option::Some(name) => Location::Register(name),
+            Option::None => Location::Stack(allocation.spill_slot(spill_base))
+        };
+        colors.insert(node, location);
+    }
+
+    for vreg in 0..function.next_vreg {
+        let node : u32 = graph.find(vreg);
+        if let Option::Some(location) = colors.get(&node) {
+            allocation.locations.insert(vreg, location.clone());
+        }
+    }
+    return allocation;
+}
+
+fn spill_cost(graph : &InterferenceGraph, degrees : &HashMap<u32, usize>, node : u32) -> f64 {
+    return graph.occurrences[&node] as f64 / (degrees[&node] + 1) as f64;
+}
@@ -4,10 +4,9 @@ it will combine both the intermediate and target code generation into one unit o
 logic
 */
 
-use std::{fs, rc::Rc};
+use std::fs;
 
-use crate::{parse_c::{Node, NodeType}, token_c::is_identifier};
-use crate::symbol_table_c::{*};
+use crate::ast_c::{*};
 
 static mut CURRENT_TEMP_REG : u128 = 0;
 
@@ -20,208 +19,134 @@ fn get_reg_and_inc() -> u128 {
 }
 
 
-pub fn generate_ir(filename : &String, current_node : &mut Node, symbol_table : &Rc<STNode>) {
+pub fn generate_ir(filename : &String, program : &Program) {
     let mut ir_string : String = "".to_string();
 
-    gen_ir(&mut ir_string, current_node, symbol_table);
+    for decl in &program.decls {
+        match decl {
+            Decl::Function(function) => gen_function(&mut ir_string, function),
+            Decl::Prototype(prototype) => gen_prototype(&mut ir_string, prototype)
+        }
+    }
 
     fs::write(filename, ir_string).expect("Unable to write to file");
 
 }
 
+fn gen_prototype(ir_string : &mut String, prototype : &Prototype) {
+    ir_string.push_str(format!("declare {} @{}(", to_primitive(&prototype.return_type), prototype.name).as_str());
+    gen_params(ir_string, &prototype.params);
+    ir_string.push_str(")\n");
+}
 
-fn gen_ir(ir_string : &mut String, current_node : &mut Node, symbol_table : &Rc<STNode>) {
-    match current_node.node_type {
-        NodeType::Func_Decl => {
-            let func_name : String = current_node.children[1].properties["value"].clone();
-            //TODO: Need to mangle return type correctly to match LLVM syntax
-            let primitive : String = symbol_table.scope_lookup(&func_name).unwrap().primitive.clone();
-            ir_string.push_str(format!("define {} @{}()", to_primitive(primitive), func_name).as_str());
-
-            ir_string.push_str("{\n");
-
-            let current_symbol_table: &Rc<STNode> = &symbol_table.children.borrow()[*symbol_table.scope_index.borrow()];
-            generate_children(ir_string, current_node, current_symbol_table);
-            *symbol_table.scope_index.borrow_mut() += 1;
-            
-            ir_string.push_str("}\n");
-        }
-        NodeType::Arguments => {
-            if current_node.properties.contains_key("primtive") {
-                ir_string.push_str(format!("{} {}", current_node.properties["primitive"], current_node.properties["identifier"]).as_str());
-                if current_node.children[3].children.len() > 0 {
-                    //If there are more arguments to print, insert a comma
-                    ir_string.push_str(",");
-                }
-            }
-        }
-        NodeType::Assign_Expr => {
-            let arith_expr : &mut Node = &mut current_node.children[2];
-            gen_ir(ir_string, arith_expr, symbol_table);
-            let reg_name  = arith_expr.properties["register"].clone();
+fn gen_function(ir_string : &mut String, function : &Function) {
+    //TODO: Need to mangle return type correctly to match LLVM syntax
+    ir_string.push_str(format!("define {} @{}(", to_primitive(&function.return_type), function.name).as_str());
+    gen_params(ir_string, &function.params);
+    ir_string.push_str(")");
 
-            let identifier_node : &mut Node = &mut current_node.children[0];
-            let identifier : String = identifier_node.properties["value"].clone();
+    ir_string.push_str("{\n");
 
-            ir_string.push_str(format!("\t%{} = {}\n", identifier, reg_name).as_str());
+    gen_body(ir_string, &function.body);
+    
+    ir_string.push_str("}\n");
+}
 
-            current_node.properties.insert("register".to_string(), reg_name.clone());
-            
+fn gen_params(ir_string : &mut String, params : &Vec<Variable>) {
+    let mut index : usize = 0;
+    while index < params.len() {
+        let param : &Variable = &params[index];
+        ir_string.push_str(format!("{} {}", to_primitive(&param.primitive), param.name).as_str());
+        if index + 1 < params.len() {
+            //If there are more arguments to print, insert a comma
+            ir_string.push_str(",");
         }
-        NodeType::Arith_Expr => {
-            assert!(current_node.properties.contains_key("terminal"));
-
-            //Left operand is a constant
-                
-            //Move it into a register
-            let factor_node : &mut Node = &mut current_node.children[0];
-            gen_ir(ir_string, factor_node, symbol_table);
-            let reg_name : String = factor_node.properties["register"].clone();
-
-            let mut subterm_node : &mut Node = &mut current_node.children[1];
+        index += 1;
+    }
+}
 
-            subterm_node.properties.insert("prev_register".to_string(), reg_name.clone());
+fn gen_body(ir_string : &mut String, body : &Vec<Stmt>) {
+    for stmt in body {
+        gen_stmt(ir_string, stmt);
+    }
+}
 
-            gen_ir(ir_string, &mut subterm_node, symbol_table);
-            let result_reg : String = 
-            if current_node.children[1].properties.contains_key("register") {
-                current_node.children[1].properties["register"].clone()
-            }
-            else {
-                reg_name
-            };
-            current_node.properties.insert("register".to_string(), result_reg);
+fn gen_stmt(ir_string : &mut String, stmt : &Stmt) {
+    match stmt {
+        Stmt::VarDecl{var, init : Option::Some(value), ..} |
+        Stmt::Assign{target : var, value, ..} => {
+            let reg_name : String = gen_expr(ir_string, value);
 
+            ir_string.push_str(format!("\t%{} = {}\n", var.name, reg_name).as_str());
         }
-        NodeType::Arith_Subexpr => {
-            //Addition and subtraction happen here
-            if current_node.properties.contains_key("operator") {
-                let operator : String = current_node.properties["operator"].clone();
-
-                /* Generate term node completely, so all multiplication, division, and parenthesis
-                are given priority before addition, subtraction */
-                let term_node : &mut Node = &mut current_node.children[1];
-                gen_ir(ir_string, term_node, symbol_table);
-
-                let result_reg : String = term_node.properties["register"].clone();
-                let prev_reg : String = current_node.properties["prev_register"].clone();
-
-                ir_string.push_str(format!("\t{} = {} {} {}\n",prev_reg, to_operator(operator), result_reg, prev_reg).as_str());
-                current_node.children[2].properties.insert("prev_register".to_string(), prev_reg.clone());
-                current_node.properties.insert("register".to_string(), prev_reg);
-
-                let subexpr_node : &mut Node = &mut current_node.children[2];
-                gen_ir(ir_string, subexpr_node, symbol_table);
+        Stmt::If{branches, else_body, ..} => {
+            for branch in branches {
+                gen_body(ir_string, &branch.body);
             }
-            else if current_node.properties.contains_key("terminal"){
-                let term_node : &mut Node = &mut current_node.children[0];
-                gen_ir(ir_string, term_node, symbol_table);
-
-                let result_reg : String = term_node.properties["register"].clone();
-                current_node.properties.insert("register".to_string(), result_reg.clone());
+            if let Option::Some(body) = else_body {
+                gen_body(ir_string, body);
             }
         }
-        NodeType::Arith_Term => {
-            assert!(current_node.properties.contains_key("terminal"));
-
-            //Left operand is a constant
-                
-            //Move it into a register
-            let factor_node : &mut Node = &mut current_node.children[0];
-            gen_ir(ir_string, factor_node, symbol_table);
-            let reg_name : String = factor_node.properties["register"].clone();
-
-            let mut subterm_node : &mut Node = &mut current_node.children[1];
-
-            subterm_node.properties.insert("prev_register".to_string(), reg_name.clone());
-
-            gen_ir(ir_string, &mut subterm_node, symbol_table);
-            let result_reg : String = 
-            if current_node.children[1].properties.contains_key("register") {
-                current_node.children[1].properties["register"].clone()
-            }
-            else {
-                reg_name
-            };
-            current_node.properties.insert("register".to_string(), result_reg);
+        Stmt::While{body, ..} | Stmt::DoWhile{body, ..} => {
+            gen_body(ir_string, body);
         }
-        NodeType::Arith_Subterm => {
-            //Multiplication and division happen here
-            if current_node.properties.contains_key("operator") {
-                let operator : String = current_node.properties["operator"].clone();
-
-                /* Generate term node completely, so all multiplication, division, and parenthesis
-                are given priority before addition, subtraction */
-                let factor_node : &mut Node = &mut current_node.children[1];
-                gen_ir(ir_string, factor_node, symbol_table);
-
-                let result_reg : String = factor_node.properties["register"].clone();
-                let prev_reg : String = current_node.properties["prev_register"].clone();
-
-                ir_string.push_str(format!("\t{} = {} {} {}\n",prev_reg, to_operator(operator), result_reg, prev_reg).as_str());
-                current_node.children[2].properties.insert("prev_register".to_string(), prev_reg.clone());
-                current_node.properties.insert("register".to_string(), prev_reg);
-
-                let subterm_node : &mut Node = &mut current_node.children[2];
-                gen_ir(ir_string, subterm_node, symbol_table);
+        Stmt::For{init, step, body, ..} => {
+            if let Option::Some(init) = init {
+                gen_stmt(ir_string, init);
             }
-            else if current_node.properties.contains_key("terminal"){
-                let factor_node : &mut Node = &mut current_node.children[0];
-                gen_ir(ir_string, factor_node, symbol_table);
-
-                let result_reg : String = factor_node.properties["register"].clone();
-                current_node.properties.insert("register".to_string(), result_reg.clone());
+            gen_body(ir_string, body);
+            if let Option::Some(step) = step {
+                gen_stmt(ir_string, step);
             }
         }
-        NodeType::Arith_Factor => {
-            let current_reg : u128 = get_reg_and_inc();
-            let mut factor : String = "".to_string();
-            let operand : String = current_node.properties["terminal"].clone();
-            if is_identifier(&operand) {
-                //We have different behavior according to whether or not this factor is a function
-                if symbol_table.scope_lookup(&operand).unwrap().func {
-                    //TODO: Insert a function call here
-                }
-                else {
-                    factor = format!("%{}", operand);
-                }
-            }
-            else {
-                factor = format!("s{}", operand);
+        Stmt::Switch{cases, ..} => {
+            for case in cases {
+                gen_body(ir_string, &case.body);
             }
-            ir_string.push_str(format!("\t%{} = {}\n", current_reg.to_string(), factor).as_str());
-            current_node.properties.insert("register".to_string(), format!("%{}", current_reg));
-
-        }
-        _ => {
-            generate_children(ir_string, current_node, symbol_table);
         }
-
+        _ => {}
     }
 }
 
-fn generate_children(ir_string : &mut String, current_node : &mut Node, symbol_table : &Rc<STNode>) {
-    for mut node in &mut current_node.children {
-        gen_ir(ir_string, &mut node, symbol_table);
+fn gen_expr(ir_string : &mut String, expr : &Expr) -> String {
+    match &expr.kind {
+        ExprKind::Binary{op, lhs, rhs} => {
+            //Both operands are evaluated left to right before the operator is applied to them in the same order
+            let lhs_reg : String = gen_expr(ir_string, lhs);
+            let rhs_reg : String = gen_expr(ir_string, rhs);
+
+            let result_reg : u128 = get_reg_and_inc();
+            ir_string.push_str(format!("\t%{} = {} {}, {}\n", result_reg, to_operator(op), lhs_reg, rhs_reg).as_str());
+            return format!("%{}", result_reg);
+        }
+        _ => {
+            let current_reg : u128 = get_reg_and_inc();
+            let factor : String = match &expr.kind {
+                ExprKind::Variable(var) => format!("%{}", var.name),
+                ExprKind::Constant(value) => format!("s{}", value),
+                //TODO: Insert a function call here
+                _ => "".to_string()
+            };
+            ir_string.push_str(format!("\t%{} = {}\n", current_reg.to_string(), factor).as_str());
+            return format!("%{}", current_reg);
+        }
     }
 }
 
-fn to_operator(operation : String) -> String {
-    let op = operation.as_str();
-    match op {
-        "+" => "add".to_string(),
-        "-" => "sub".to_string(),
-        "*" => "mul".to_string(),
-        "/" => "div".to_string(),
+fn to_operator(operation : &BinaryOp) -> String {
+    match operation {
+        BinaryOp::Add => "add".to_string(),
+        BinaryOp::Sub => "sub".to_string(),
+        BinaryOp::Mul => "mul".to_string(),
+        BinaryOp::Div => "div".to_string(),
         _ => "Error: Invalid operation passed".to_string()
     }
 }
 
-fn to_primitive(primitive : String) -> String {
-    let op = primitive.as_str();
-    match op {
-        "int" => "i32".to_string(),
-        "bool" => "i1".to_string(),
+fn to_primitive(primitive : &Primitive) -> String {
+    match primitive {
+        Primitive::Int => "i32".to_string(),
+        Primitive::Bool => "i1".to_string(),
         _ => "Error: Invalid primitive passed".to_string()
     }
-}
\ No newline at end of file
+}
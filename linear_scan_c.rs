@@ -0,0 +1,116 @@
+/* 
+Linear scan register allocation, following Poletto and Sarkar. Live intervals are
+visited in the order they start, registers of intervals that already ended are
+handed back, and when no register is free the interval that ends last is spilled
+to a stack slot for its whole lifetime.
+*/
+
+use crate::machine_c::{*};
+
+/* 
+Allocates registers for a function whose frame already uses spill_base slots.
+An interval that is live across a call prefers a callee-saved register, since a
+caller-saved one has to be saved and restored around every call it crosses.
+Intervals that don't cross a call take caller-saved registers first, so the
+callee-saved ones stay available.
+*/
+pub fn linear_scan(function : &MachineFunction, register_manager : &RegisterManager, spill_base : u32) -> Allocation {
+    let live_out : Vec<_> = live_out(function);
+    let intervals : Vec<Interval> = live_intervals(function, &live_out);
+
+    let mut free_caller_saved : Vec<String> = register_manager.allocatable(false);
+    let mut free_callee_saved : Vec<String> = register_manager.allocatable(true);
+    //Intervals currently holding a register
+    let mut active : Vec<(Interval, String)> = Vec::new();
+    let mut allocation : Allocation = Allocation::new();
+
+    for interval in intervals {
+        //Give back the registers of every interval that ended before this one starts
+        let mut index : usize = 0;
+        while index < active.len() {
+            if active[index].0.end < interval.start {
+                let (_, name) = active.remove(index);
+                if register_manager.is_callee_saved(&name) {
+                    free_callee_saved.push(name);
+                }
+                else {
+                    free_caller_saved.push(name);
+                }
+            }
+            else {
+                index += 1;
+            }
+        }
+
+        //A copy whose source dies at the copy takes over the source's register, so the copy disappears
+        if let Option::Some(index) = copy_source(function, &interval, &active, register_manager) {
+            let (_, name) = active.remove(index);
+            allocation.locations.insert(interval.vreg, Location::Register(name.clone()));
+            active.push((interval, name));
+            continue;
+        }
+
+        let (preferred, fallback) : (&mut Vec<String>, &mut Vec<String>) =
+        if interval.crosses_call {
+            (&mut free_callee_saved, &mut free_caller_saved)
+        }
+        else {
+            (&mut free_caller_saved, &mut free_callee_saved)
+        };
+        let register : Option<String> =
+        if !preferred.is_empty() {
+            Option::Some(preferred.remove(0))
+        }
+        else if !fallback.is_empty() {
+            Option::Some(fallback.remove(0))
+        }
+        else {
+            Option::None
+        };
+
+        if let Option::Some(name) = register {
+            allocation.locations.insert(interval.vreg, Location::Register(name.clone()));
+            active.push((interval, name));
+            continue;
+        }
+
+        let mut victim : Option<usize> = Option::None;
+        for (index, (other, _)) in active.iter().enumerate() {
+            if victim.is_none() || other.end > active[victim.unwrap()].0.end {
+                victim = Option::Some(index);
+            }
+        }
+
+        let slot : i32 = allocation.spill_slot(spill_base);
+        match victim {
+            Option::Some(index) if active[index].0.end > interval.end => {
+                let (other, name) = active.remove(index);
+                allocation.locations.insert(other.vreg, Location::Stack(slot));
+                allocation.locations.insert(interval.vreg, Location::Register(name.clone()));
+                active.push((interval, name));
+            }
+            _ => {
+                allocation.locations.insert(interval.vreg, Location::Stack(slot));
+            }
+        }
+    }
+
+    return allocation;
+}
+
+/* 
+Index of the active interval that interval is copied from, if that interval ends
+at the copy. A caller-saved register is not taken over by an interval that crosses
+a call, since a callee-saved one is cheaper for it.
+*/
+fn copy_source(function : &MachineFunction, interval : &Interval, active : &[(Interval, String)], register_manager : &RegisterManager) -> Option<usize> {
+    let instr : &Instr = &function.instrs[interval.start];
+    if instr.kind != InstrKind::Move || instr.defs[0] != interval.vreg {
+        return Option::None;
+    }
+    return active.iter().position(|(other, name)|
+        other.vreg == instr.uses[0] &&
+        other.end == interval.start &&
+        (!interval.crosses_call || register_manager.is_callee_saved(name))
+    );
+}
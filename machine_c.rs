@@ -0,0 +1,419 @@
+/* 
+This file describes x86 code over virtual registers. Code generation emits the
+instructions of a whole function with as many virtual registers as it likes, and
+a register allocator later maps each of them to a physical register or a stack
+slot. Liveness is computed here, since every allocator needs it.
+*/
+
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, PartialEq)]
+pub enum InstrKind {
+    Normal,
+    //Copies one virtual register into another, which allocators try to make free
+    Move,
+    Label(String),
+    Jump(String),
+    CondJump(String),
+    //Indirect jump through a table, which may go to any of the labels
+    JumpTable(Vec<String>),
+    //Clobbers every caller-saved register
+    Call,
+    //Leaves the function, so nothing is live afterwards
+    Return
+}
+
+/* 
+A single instruction, or a short sequence that has to stay together. Virtual
+registers appear in the text as %v<number>, %d<number> or %b<number> depending
+on whether all 64 bits, the low 32 bits or the low byte of the register are meant.
+defs and uses list them so liveness does not have to parse the text.
+*/
+#[derive(Clone)]
+pub struct Instr {
+    pub kind : InstrKind,
+    pub text : String,
+    pub defs : Vec<u32>,
+    pub uses : Vec<u32>
+}
+
+impl Instr {
+    pub fn new(text : String, defs : Vec<u32>, uses : Vec<u32>) -> Instr {
+        return Instr{kind : InstrKind::Normal, text, defs, uses};
+    }
+
+    pub fn copy(dst : u32, src : u32) -> Instr {
+        return Instr{kind : InstrKind::Move, text : format!("\tmov {}, {}\n", vreg_name(dst), vreg_name(src)), defs : vec![dst], uses : vec![src]};
+    }
+
+    pub fn with_kind(kind : InstrKind, text : String) -> Instr {
+        return Instr{kind, text, defs : Vec::new(), uses : Vec::new()};
+    }
+
+    //Every virtual register the instruction mentions, in order and without repeats
+    pub fn vregs(&self) -> Vec<u32> {
+        let mut vregs : Vec<u32> = Vec::new();
+        for vreg in self.uses.iter().chain(self.defs.iter()) {
+            if !vregs.contains(vreg) {
+                vregs.push(*vreg);
+            }
+        }
+        return vregs;
+    }
+}
+
+pub fn vreg_name(vreg : u32) -> String {
+    return format!("%v{}", vreg);
+}
+
+pub fn vreg_dword(vreg : u32) -> String {
+    return format!("%d{}", vreg);
+}
+
+pub fn vreg_byte(vreg : u32) -> String {
+    return format!("%b{}", vreg);
+}
+
+//Name of the low size bytes of a 64 bit register, such as eax or al for rax
+pub fn sub_register(name : &str, size : u32) -> String {
+    let numbered : bool = name[1..].starts_with(|c : char| c.is_ascii_digit());
+    return match size {
+        4 if numbered => format!("{}d", name),
+        4 => format!("e{}", &name[1..]),
+        1 if numbered => format!("{}b", name),
+        1 if name.ends_with('x') => format!("{}l", &name[1..2]),
+        1 => format!("{}l", &name[1..]),
+        _ => name.to_string()
+    };
+}
+
+//Replaces every virtual register in text with the name chosen for it, cut down to the size it is used at
+pub fn substitute(text : &str, name_of : &dyn Fn(u32) -> String) -> String {
+    let mut result : String = "".to_string();
+    let mut rest : &str = text;
+
+    while let Option::Some(start) = rest.find('%') {
+        result.push_str(&rest[..start]);
+        let size : u32 = match &rest[start + 1..start + 2] {
+            "d" => 4,
+            "b" => 1,
+            _ => 8
+        };
+        let digits : usize = rest[start + 2..].chars().take_while(|c| c.is_ascii_digit()).count();
+        let vreg : u32 = rest[start + 2..start + 2 + digits].parse::<u32>().unwrap();
+        result.push_str(sub_register(name_of(vreg).as_str(), size).as_str());
+        rest = &rest[start + 2 + digits..];
+    }
+    result.push_str(rest);
+    return result;
+}
+
+//The instructions of one function, together with the next unused virtual register and label
+pub struct MachineFunction {
+    pub instrs : Vec<Instr>,
+    pub next_vreg : u32,
+    //Labels are numbered across the whole program, since they all end up in one file
+    pub next_label : u32,
+    //Constant data the instructions refer to, such as jump tables, which goes in a read-only section
+    pub read_only : String
+}
+
+impl MachineFunction {
+    pub fn new(next_label : u32) -> MachineFunction {
+        return MachineFunction{instrs : Vec::new(), next_vreg : 0, next_label, read_only : "".to_string()};
+    }
+
+    pub fn vreg(&mut self) -> u32 {
+        self.next_vreg += 1;
+        return self.next_vreg - 1;
+    }
+
+    pub fn label(&mut self) -> u32 {
+        self.next_label += 1;
+        return self.next_label - 1;
+    }
+
+    pub fn push(&mut self, instr : Instr) {
+        self.instrs.push(instr);
+    }
+
+    //Emits an instruction that uses no virtual registers
+    pub fn push_text(&mut self, text : String) {
+        self.instrs.push(Instr::new(text, Vec::new(), Vec::new()));
+    }
+}
+
+pub struct Register {
+    pub name : String,
+    //Reserved registers are never handed out by an allocator
+    pub in_use : bool,
+    //Whether a function has to restore the register before returning
+    pub callee_saved : bool
+}
+
+//The x86-64 register file, as seen by the register allocators
+pub struct RegisterManager {
+    pub register_list : Vec<Register>
+}
+
+impl RegisterManager {
+
+    pub fn new() -> RegisterManager {
+        let mut register_manager : RegisterManager = RegisterManager{register_list : Vec::new()};
+        register_manager.initialize();
+        return register_manager;
+    }
+
+    fn initialize(&mut self) {
+        //Hardcoded register names specifically for x86 architecture
+        //rax and rdx are overwritten by imul, idiv and call, so they are never handed out
+        self.register_list.push(Register{name : "rax".to_string(), in_use : true, callee_saved : false});
+        self.register_list.push(Register{name : "rbx".to_string(), in_use : false, callee_saved : true});
+        self.register_list.push(Register{name : "rcx".to_string(), in_use : false, callee_saved : false});
+        self.register_list.push(Register{name : "rdx".to_string(), in_use : true, callee_saved : false});
+        self.register_list.push(Register{name : "rsi".to_string(), in_use : false, callee_saved : false});
+        self.register_list.push(Register{name : "rdi".to_string(), in_use : false, callee_saved : false});
+
+        //Making sure that rsp, rbp cannot be overwritten by marking them in use
+        self.register_list.push(Register{name : "rbp".to_string(), in_use : true, callee_saved : true});
+        self.register_list.push(Register{name : "rsp".to_string(), in_use : true, callee_saved : true});
+
+        self.register_list.push(Register{name : "r8".to_string(), in_use : false, callee_saved : false});
+        self.register_list.push(Register{name : "r9".to_string(), in_use : false, callee_saved : false});
+
+        //r10 and r11 are kept free for loading and storing spilled virtual registers
+        self.register_list.push(Register{name : "r10".to_string(), in_use : true, callee_saved : false});
+        self.register_list.push(Register{name : "r11".to_string(), in_use : true, callee_saved : false});
+
+        self.register_list.push(Register{name : "r12".to_string(), in_use : false, callee_saved : true});
+        self.register_list.push(Register{name : "r13".to_string(), in_use : false, callee_saved : true});
+        self.register_list.push(Register{name : "r14".to_string(), in_use : false, callee_saved : true});
+        self.register_list.push(Register{name : "r15".to_string(), in_use : false, callee_saved : true});
+    }
+
+    //Registers an allocator may hand out, caller-saved ones first
+    pub fn allocatable(&self, callee_saved : bool) -> Vec<String> {
+        return self.register_list.iter()
+            .filter(|register| !register.in_use && register.callee_saved == callee_saved)
+            .map(|register| register.name.clone())
+            .collect();
+    }
+
+    pub fn is_callee_saved(&self, name : &String) -> bool {
+        return self.register_list.iter().any(|register| register.name == *name && register.callee_saved);
+    }
+
+    pub fn scratch_registers(&self) -> [&str; 2] {
+        return ["r10", "r11"];
+    }
+}
+
+/* 
+Virtual registers that are live right after each instruction. Instructions are
+grouped into basic blocks at labels and after jumps, the blocks are solved with
+the usual backwards dataflow until nothing changes, and the result is then
+spread back over the instructions of each block.
+*/
+pub fn live_out(function : &MachineFunction) -> Vec<HashSet<u32>> {
+    let instrs : &Vec<Instr> = &function.instrs;
+
+    let mut block_starts : Vec<usize> = Vec::new();
+    for (index, instr) in instrs.iter().enumerate() {
+        let after_branch : bool = index > 0 && matches!(instrs[index - 1].kind, InstrKind::Jump(_) | InstrKind::CondJump(_) | InstrKind::JumpTable(_) | InstrKind::Return);
+        if index == 0 || after_branch || matches!(instr.kind, InstrKind::Label(_)) {
+            block_starts.push(index);
+        }
+    }
+
+    let mut label_blocks : HashMap<String, usize> = HashMap::new();
+    for (block, start) in block_starts.iter().enumerate() {
+        if let InstrKind::Label(name) = &instrs[*start].kind {
+            label_blocks.insert(name.clone(), block);
+        }
+    }
+
+    let block_end = |block : usize| -> usize {
+        return if block + 1 < block_starts.len() {block_starts[block + 1]} else {instrs.len()};
+    };
+
+    let mut successors : Vec<Vec<usize>> = Vec::new();
+    for block in 0..block_starts.len() {
+        let last : &Instr = &instrs[block_end(block) - 1];
+        let mut next : Vec<usize> = Vec::new();
+        match &last.kind {
+            InstrKind::Jump(target) => next.push(label_blocks[target]),
+            InstrKind::JumpTable(targets) => next.extend(targets.iter().map(|target| label_blocks[target])),
+            InstrKind::CondJump(target) => {
+                next.push(label_blocks[target]);
+                if block + 1 < block_starts.len() {
+                    next.push(block + 1);
+                }
+            }
+            InstrKind::Return => {}
+            _ => {
+                if block + 1 < block_starts.len() {
+                    next.push(block + 1);
+                }
+            }
+        }
+        successors.push(next);
+    }
+
+    let mut block_live_in : Vec<HashSet<u32>> = vec![HashSet::new(); block_starts.len()];
+    let mut block_live_out : Vec<HashSet<u32>> = vec![HashSet::new(); block_starts.len()];
+    let mut changed : bool = true;
+    while changed {
+        changed = false;
+        for block in (0..block_starts.len()).rev() {
+            let mut live : HashSet<u32> = HashSet::new();
+            for successor in &successors[block] {
+                live.extend(block_live_in[*successor].iter());
+            }
+            block_live_out[block] = live.clone();
+
+            for instr in instrs[block_starts[block]..block_end(block)].iter().rev() {
+                transfer(&mut live, instr);
+            }
+            if live != block_live_in[block] {
+                block_live_in[block] = live;
+                changed = true;
+            }
+        }
+    }
+
+    let mut live_out : Vec<HashSet<u32>> = vec![HashSet::new(); instrs.len()];
+    for block in 0..block_starts.len() {
+        let mut live : HashSet<u32> = block_live_out[block].clone();
+        for index in (block_starts[block]..block_end(block)).rev() {
+            live_out[index] = live.clone();
+            transfer(&mut live, &instrs[index]);
+        }
+    }
+    return live_out;
+}
+
+//Turns the set of registers live after instr into the set live before it
+fn transfer(live : &mut HashSet<u32>, instr : &Instr) {
+    for def in &instr.defs {
+        live.remove(def);
+    }
+    live.extend(instr.uses.iter());
+}
+
+//Range of instruction indices a virtual register has to keep its location over
+#[derive(Clone, Copy)]
+pub struct Interval {
+    pub vreg : u32,
+    pub start : usize,
+    pub end : usize,
+    //Whether a call happens while the register is live
+    pub crosses_call : bool
+}
+
+pub fn live_intervals(function : &MachineFunction, live_out : &[HashSet<u32>]) -> Vec<Interval> {
+    let mut ranges : HashMap<u32, (usize, usize)> = HashMap::new();
+    let mut extend = |vreg : u32, index : usize| {
+        let range : &mut (usize, usize) = ranges.entry(vreg).or_insert((index, index));
+        range.0 = range.0.min(index);
+        range.1 = range.1.max(index);
+    };
+
+    for (index, instr) in function.instrs.iter().enumerate() {
+        for vreg in instr.vregs() {
+            extend(vreg, index);
+        }
+        for vreg in &live_out[index] {
+            extend(*vreg, index);
+        }
+    }
+
+    let calls : Vec<usize> = function.instrs.iter().enumerate()
+        .filter(|(_, instr)| instr.kind == InstrKind::Call)
+        .map(|(index, _)| index)
+        .collect();
+
+    let mut intervals : Vec<Interval> = ranges.iter().map(|(vreg, (start, end))| Interval{
+        vreg : *vreg,
+        start : *start,
+        end : *end,
+        crosses_call : calls.iter().any(|call| start < call && call < end)
+    }).collect();
+    intervals.sort_by_key(|interval| (interval.start, interval.vreg));
+    return intervals;
+}
+
+//Where an allocator put a virtual register
+#[derive(Clone, PartialEq)]
+pub enum Location {
+    Register(String),
+    Stack(i32)
+}
+
+pub struct Allocation {
+    pub locations : HashMap<u32, Location>,
+    //Number of stack slots used for spilled virtual registers
+    pub spill_slots : u32
+}
+
+impl Allocation {
+    pub fn new() -> Allocation {
+        return Allocation{locations : HashMap::new(), spill_slots : 0};
+    }
+
+    //Hands out a new slot below the first spill_base slots of the frame
+    pub fn spill_slot(&mut self, spill_base : u32) -> i32 {
+        self.spill_slots += 1;
+        return (spill_base + self.spill_slots) as i32 * -8;
+    }
+}
+
+/* 
+Writes out the function with every virtual register replaced by its location.
+A spilled register is loaded into a scratch register before the instruction and
+stored back afterwards if the instruction writes it. Copies between registers
+that ended up in the same place are left out.
+*/
+pub fn rewrite(function : &MachineFunction, locations : &HashMap<u32, Location>, register_manager : &RegisterManager) -> String {
+    let mut program_string : String = "".to_string();
+
+    for instr in &function.instrs {
+        if instr.kind == InstrKind::Move && locations[&instr.defs[0]] == locations[&instr.uses[0]] {
+            continue;
+        }
+
+        let mut names : HashMap<u32, String> = HashMap::new();
+        let mut stores : Vec<(String, i32)> = Vec::new();
+        let mut scratch : usize = 0;
+
+        for vreg in instr.vregs() {
+            match &locations[&vreg] {
+                Location::Register(name) => {
+                    names.insert(vreg, name.clone());
+                }
+                Location::Stack(addr) => {
+                    let scratch_name : String = register_manager.scratch_registers()[scratch].to_string();
+                    scratch += 1;
+                    if instr.uses.contains(&vreg) {
+                        program_string.push_str(format!("\tmov {}, {}\n", scratch_name, stack_location(*addr)).as_str());
+                    }
+                    if instr.defs.contains(&vreg) {
+                        stores.push((scratch_name.clone(), *addr));
+                    }
+                    names.insert(vreg, scratch_name);
+                }
+            }
+        }
+
+        program_string.push_str(substitute(&instr.text, &|vreg : u32| names[&vreg].clone()).as_str());
+        for (scratch_name, addr) in stores {
+            program_string.push_str(format!("\tmov qword {}, {}\n", stack_location(addr), scratch_name).as_str());
+        }
+    }
+    return program_string;
+}
+
+//Memory operand for a stack slot at the given offset from rbp
+pub fn stack_location(addr : i32) -> String {
+    let operator : String = if addr > 0{"+".to_string()} else {"".to_string()};
+    return format!("[rbp{}{}]", operator, addr);
+}
@@ -5,65 +5,97 @@ mod code_gen_c;
 mod expression_c;
 mod statement_c;
 mod symbol_table_c;
+mod diagnostic_c;
+mod ast_c;
+mod machine_c;
+mod linear_scan_c;
+mod graph_coloring_c;
 // mod ir_gen_c;
 
 // use crate::ir_gen_c::generate_ir;
 use crate::token_c::{lex_file, Token};
-use crate::code_gen_c::generate_code;
-use crate::parse_c::{parse, create_node, Node, NodeType};
+use crate::code_gen_c::{generate_code, Allocator};
+use crate::parse_c::{parse, create_node, Node, NodeType, Parser};
 use crate::symbol_table_c::{*};
+use crate::diagnostic_c::{Diagnostic, SourceMap, render_diagnostic};
+use crate::ast_c::{lower_program, Program};
 use std::env;
+use std::process;
 
 fn main() {
 
-    let args : Vec<String> = env::args().collect();
+    //Options start with `--` and may appear anywhere, everything else is positional
+    let (options, args) : (Vec<String>, Vec<String>) = env::args().partition(|arg| arg.starts_with("--"));
+
+    let mut allocator : Allocator = Allocator::LinearScan;
+    for option in &options {
+        match option.strip_prefix("--regalloc=").and_then(Allocator::from_name) {
+            Option::Some(chosen) => allocator = chosen,
+            Option::None => {
+                eprintln!("Unknown option \"{}\", the register allocator is picked with --regalloc=<linear-scan|graph-coloring>", option);
+                process::exit(1);
+            }
+        }
+    }
+
     if args.len() < 2 {
         println!("Please enter the file you want to compile");
         return;
     }
     else if args.len() > 3 {
-        println!("The format of the input is \"./compiler <file for compilation> (optional)<name of output file>\"");
+        println!("The format of the input is \"./compiler <file for compilation> (optional)<name of output file> (optional)--regalloc=<linear-scan|graph-coloring>\"");
         return
     }
 
-    let symbol_table = create_new_stnode(1);
+    let symbol_table = create_new_stnode();
+    let mut source_map : SourceMap = SourceMap::new();
     
 
-    let token_list : Vec<Token> = lex(args[1].as_str());
+    let token_list : Vec<Token> = lex(args[1].as_str(), &mut source_map);
+    let mut parser : Parser = Parser::new(token_list);
     let mut current_node : Node = create_node(NodeType::Program_Start);
 
-    if parse(&mut current_node, &token_list, &symbol_table) {
-        let filename : String;
-        if args.len() == 3 {
-            filename = args[2].clone();
-        }
-        else {
-            filename = "a.asm".to_string();
-        }
+    let parsed : bool = parse(&mut current_node, &mut parser, &symbol_table);
+    if !parsed && parser.error_count() == 0 {
+        //Nothing more specific was reported, so the problem is with the syntax itself
+        let syntax_error : Diagnostic = parser.syntax_error();
+        parser.report(syntax_error);
+    }
 
-        let _filename_ir : String = "main_generated.ll".to_string();
-        // generate_ir(&filename_ir, &mut current_node, &symbol_table);
-        generate_code(&filename, &mut current_node, &symbol_table);
+    for diagnostic in &parser.diagnostics {
+        eprintln!("{}", render_diagnostic(diagnostic, &source_map));
     }
-    else {
-        println!("Sorry there was a parsing error!");
+
+    if parser.error_count() > 0 {
+        eprintln!("error: could not compile `{}` due to {} previous {}", args[1], parser.error_count(), if parser.error_count() == 1 {"error"} else {"errors"});
+        process::exit(1);
     }
 
-    
+    let filename : String = if args.len() == 3 {
+        args[2].clone()
+    }
+    else {
+        "a.asm".to_string()
+    };
+
+    let program : Program = lower_program(&current_node);
+
+    let _filename_ir : String = "main_generated.ll".to_string();
+    // generate_ir(&filename_ir, &program);
+    generate_code(&filename, &program, allocator);
 }
 
 
 /* 
 Pass a source file to this function to receive a list of all tokens contained in
-the source file.
+the source file. The file is also added to the source map so diagnostics can
+show the lines they point at.
 */
-fn lex(src : &str) -> Vec<Token> {
+fn lex(src : &str, source_map : &mut SourceMap) -> Vec<Token> {
 
     
     let contents : String = fs::read_to_string(src).expect("Should have been able to read from file");   
 
-    return lex_file(contents);
+    let file_id : u32 = source_map.add_file(src.to_string(), contents.clone());
+    return lex_file(contents, file_id);
 }
-
-
-
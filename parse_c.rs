@@ -11,7 +11,7 @@ This is the grammer that is currently being implemeted by the parser:
     Assume that functions don't have parameters/arguments.
 
     program_start -> other_decl
-x   func_decl -> primitive identifier (arguments);
+    func_decl -> primitive identifier (arguments);
     func_decl -> primitive identifier (arguments){body}
     other_decl -> [func_decl other_decl] | empty
     
@@ -19,9 +19,8 @@ x   func_decl -> primitive identifier (arguments);
 
     body -> statement | statement body
 
-    assign_expr -> identifier = expr
-
-    expr -> arith_expr | condition_expr
+    assign_expr -> identifier assign_operator expr
+    assign_operator -> = | += | -= | *= | /= | %= | &= | |= | ^= | <<= | >>=
 
     optional_expr -> var_decl | assign_expr | expr | empty
 
@@ -29,114 +28,426 @@ x   func_decl -> primitive identifier (arguments);
     var_decl -> primitive identifier
     var_decl -> primitive assign_expr
 
-    statement -> assign_expr ;
     statement -> func_call ;
+    statement -> expr ;
     
     func_call -> identifier (call_args)
     call_args -> [expr, call_args] | empty
 
-    arith_expr -> arith_term subexpr
-    subexpr -> [+ arith_term subexpr] | [- arith_term subexpr] | arith_term | empty
-    arith_term -> arith_factor arith_subterm
-    arith_subterm -> [* arith_factor arith_subterm] | [/ arith_factor arith_subterm] | arith_factor | empty
-    arith_factor -> a | (arith_expr) | func_call
-
-
-    or_expr -> and_expr or_subexpr
-    or_subexpr -> [|| and_expr or_subexpr] | empty
-    and_expr -> equality_expr and_subexpr
-    and_subexpr -> [&& equality_expr and_subexpr] | empty
-    equality_expr -> relational_expr equality_subexpr
-    equality_subexpr -> [[ == | != ] relational_expr equality_subexpr] | empty
-    relational_expr -> not_expr relational_subexpr
-    relational_subexpr -> [[< | <= | > | >=] not_expr relational_subexpr] | empty
-
-    not_expr -> [! | empty] id | keyword | func_call
-
-    bool_expr -> bool_term bool_subexpr
-    bool_subexpr -> [|| bool_term bool_subexpr] | empty
-    bool_term -> bool_factor bool_subterm
-    bool_subterm -> [&& bool_factor bool_subterm] | empty
-    bool_factor -> bool_operand bool_subfactor
-    bool_subfactor -> [== | !=] bool_operand bool_subfactor | empty
-    bool_operand -> bool_atom bool_suboperand
-    bool_suboperand -> [< | <= | > | >=] bool_atom bool_suboperand | empty
-    bool_atom -> [! bool_expr] | id | keyword | func_call
-
-    relational_expr -> arith_expr [< | <= | > | >=] arith_expr
+    expr -> assign_expr | unary_expr [binary_operator unary_expr]...
+    unary_expr -> [! unary_expr] | [~ unary_expr] | [- unary_expr] | [+ unary_expr] | [++ identifier] | [-- identifier] | primary_expr
+    primary_expr -> a | a++ | a-- | keyword | func_call | (expr)
+
+    Binary operators are parsed with precedence climbing instead of one rule per
+    precedence level. From loosest to tightest binding:
+        ||
+        &&
+        |
+        ^
+        &
+        == !=
+        < <= > >=
+        << >>
+        + -
+        * / %
+    All of them are left associative, and unary operators bind tighter than any
+    of them.
 
 
     statement -> while_statement
     
-    while_statement -> keyword (condition_expr){body}
-    condition_expr -> bool_expr | relational_expr
+    while_statement -> keyword (expr) block
 
 
 
     statement -> for_statement
 
-    for_statement -> keyword (optional_expr ; optional_expr ; optional_expr) {body}
+    for_statement -> keyword (optional_expr ; optional_expr ; optional_expr) block
+
+    statement -> do_while_statement
+    do_while_statement -> keyword {body} keyword (expr) ;
+
+    statement -> switch_statement
+    switch_statement -> keyword (expr){switch_body}
+    switch_body -> [case_label body switch_body] | empty
+    case_label -> [keyword constant :] | [keyword :]
 
     statement -> if_stmt
-    if_stmt -> keyword (condition_expr){body} elif_stmt
-    elif_stmt -> [keyword(condition_expr){body} elif_stmt] | else_stmt |empty
-    else_stmt -> [keyword {body}] | empty
+    if_stmt -> keyword (expr) block elif_stmt
+    elif_stmt -> [elif_keyword (expr) block elif_stmt] | else_stmt |empty
+    elif_keyword -> elif | else if
+    else_stmt -> [keyword block] | empty
+
+    block -> {body} | statement
 
     statement -> ret_stmt
     ret_stmt -> keyword expression ;
+
+    statement -> jump_stmt
+    jump_stmt -> break ; | continue ;
 */
 
 use std::collections::HashMap;
 use std::rc::Rc;
 
-use crate::token_c::{TokenType, Token};
+use crate::token_c::{TokenType, Token, Span, is_primitive};
+use crate::diagnostic_c::Diagnostic;
 use crate::expression_c::{*};
 use crate::statement_c::{*};
 use crate::symbol_table_c::{*};
 
-static mut CURRENT_TOKEN_INDEX : u32 = 0;
+//Types the code generator can compute with, char and bool being promoted to int
+const SUPPORTED_PRIMITIVES : [&str; 3] = ["int", "bool", "char"];
 
-//This function returns the size of a primitive based on the declared type
-// fn get_primitive_size(prim : &String) -> u32 {
-//     let primitive : &str = prim.as_str();
-//     match primitive {
-//         //For now we make them all take up a register's worth of space
-//         "int" => 4,
-//         "char" => 1,
-//         "bool" => 1,
-//         _ => 0
-//     }
-// }
+//The parts of a function declaration needed to call it
+pub struct FunctionSignature {
+    pub identifier : String,
+    pub primitive : String,
+    pub params : Vec<String>,
+    pub span : Span
+}
 
+/* 
+The Parser owns everything that describes how far into the token stream a parse
+has progressed. Nothing is shared between two Parser values, so several files can
+be parsed one after the other or on separate threads.
+*/
+pub struct Parser {
+    tokens : Vec<Token>,
+    current_token_index : usize,
+    //Cursor positions saved before trying an alternative production
+    checkpoints : Vec<usize>,
+    //Returned by the lookahead functions once the end of the token stream is reached
+    eof_token : Token,
+    //Errors and warnings found so far by the parser and the semantic checks it runs
+    pub diagnostics : Vec<Diagnostic>,
+    /* 
+    The furthest token any alternative failed at, and what the failing
+    alternatives expected to see there. Since the parser backtracks, a syntax
+    error is only reported for the alternative that got the furthest.
+    */
+    furthest_failure : usize,
+    expected : Vec<String>,
+    //Number of loops around the statement being parsed, since break and continue need one
+    pub loop_depth : u32,
+    //Number of switch statements around the statement being parsed, which break can leave as well
+    pub switch_depth : u32
+}
 
-pub fn get_current_token_index() -> usize {
-    unsafe {
-        return CURRENT_TOKEN_INDEX as usize;
+impl Parser {
+
+    pub fn new(tokens : Vec<Token>) -> Parser {
+        //The end of file token sits right after the last token so errors about it point past the end
+        let eof_span : Span = match tokens.last() {
+            Option::Some(token) => Span {
+                offset : token.span.offset + token.span.length as usize,
+                column : token.span.column + token.span.length,
+                length : 0,
+                ..token.span
+            },
+            Option::None => Span{line : 1, column : 1, ..Span::default()}
+        };
+        return Parser {
+            tokens,
+            current_token_index : 0,
+            checkpoints : Vec::new(),
+            eof_token : Token{token_type : TokenType::Default, val : "".to_string(), span : eof_span},
+            diagnostics : Vec::new(),
+            furthest_failure : 0,
+            expected : Vec::new(),
+            loop_depth : 0,
+            switch_depth : 0
+        };
     }
-    
-}
 
+    pub fn next_token_index(&mut self) -> usize {
+        self.current_token_index += 1;
+        return self.current_token_index;
+    }
 
-pub fn next_token_index() -> usize {
-    unsafe {
-        CURRENT_TOKEN_INDEX += 1;
-        return CURRENT_TOKEN_INDEX as usize;
+    pub fn prev_token_index(&mut self) -> usize {
+        self.current_token_index -= 1;
+        return self.current_token_index;
     }
-    
-}
 
-pub fn prev_token_index() -> usize {
-    unsafe {
-        CURRENT_TOKEN_INDEX -= 1;
-        return CURRENT_TOKEN_INDEX as usize;
+    //Index of the current token, to be passed to span_from later
+    pub fn token_index(&self) -> usize {
+        return self.current_token_index;
+    }
+
+    pub fn at_end(&self) -> bool {
+        return self.current_token_index >= self.tokens.len();
+    }
+
+    /* 
+    Returns the span of everything consumed since start_index. If nothing was
+    consumed, an empty span at the current token is returned instead.
+    */
+    pub fn span_from(&self, start_index : usize) -> Span {
+        let start : Span = match self.tokens.get(start_index) {
+            Option::Some(token) => token.span,
+            Option::None => self.tokens.last().map(|token| token.span).unwrap_or_default()
+        };
+        if self.current_token_index <= start_index {
+            return Span{length : 0, ..start};
+        }
+        return start.to(&self.tokens[self.current_token_index - 1].span);
+    }
+
+    pub fn current_token(&self) -> &Token {
+        return self.peek_token(0);
+    }
+
+    //Looks ahead of the current token without consuming anything
+    pub fn peek_token(&self, offset : usize) -> &Token {
+        return match self.tokens.get(self.current_token_index + offset) {
+            Option::Some(token) => token,
+            Option::None => &self.eof_token
+        };
+    }
+
+    /* 
+    Checkpoints are used when a production has several alternatives. The cursor
+    is saved before trying an alternative, and restored if that alternative fails
+    part of the way through, so the next alternative starts at the same token.
+    */
+    pub fn save_checkpoint(&mut self) {
+        self.checkpoints.push(self.current_token_index);
+    }
+
+    pub fn restore_checkpoint(&mut self) {
+        self.current_token_index = self.checkpoints.pop().unwrap();
+    }
+
+    pub fn discard_checkpoint(&mut self) {
+        self.checkpoints.pop();
+    }
+
+    /* 
+    Records that the current token did not match what a production expected.
+    Only the furthest failure is kept since that is the one closest to the
+    actual mistake.
+    */
+    pub fn expect(&mut self, expected : String) {
+        if self.current_token_index > self.furthest_failure {
+            self.furthest_failure = self.current_token_index;
+            self.expected.clear();
+        }
+        if self.current_token_index == self.furthest_failure && !self.expected.contains(&expected) {
+            self.expected.push(expected);
+        }
+    }
+
+    //Creates the syntax error for the furthest point any production failed at
+    pub fn syntax_error(&self) -> Diagnostic {
+        let found_token : &Token = match self.tokens.get(self.furthest_failure) {
+            Option::Some(token) => token,
+            Option::None => &self.eof_token
+        };
+        let found : String = if found_token.val.is_empty() {
+            "end of file".to_string()
+        }
+        else {
+            format!("`{}`", found_token.val)
+        };
+
+        //Every failure should say what it expected, this is only a fallback for one that didn't
+        if self.expected.is_empty() {
+            return Diagnostic::error("E0001", format!("unexpected {}", found), found_token.span, "not expected here".to_string());
+        }
+
+        let expected : String = if self.expected.len() == 1 {
+            self.expected[0].clone()
+        }
+        else {
+            format!("one of {}", self.expected.join(", "))
+        };
+
+        return Diagnostic::error("E0001", format!("expected {}, found {}", expected, found), found_token.span, format!("expected {}", expected));
+    }
+
+    //Adds a diagnostic unless an identical one was already reported by an alternative that was backtracked
+    pub fn report(&mut self, diagnostic : Diagnostic) {
+        for reported in &self.diagnostics {
+            if reported.code == diagnostic.code && reported.primary.span == diagnostic.primary.span {
+                return;
+            }
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub fn error_count(&self) -> usize {
+        return self.diagnostics.iter().filter(|diagnostic| diagnostic.is_error()).count();
+    }
+
+    //Forgets earlier failures so the next syntax error only describes what is parsed from here on
+    pub fn reset_expected(&mut self) {
+        self.furthest_failure = self.current_token_index;
+        self.expected.clear();
+    }
+
+    /* 
+    Panic mode recovery for statements. Tokens are skipped up to and including
+    the next `;`, or up to the `}` that closes the enclosing body. Blocks opened
+    while skipping are skipped as a whole, so a broken if or while statement is
+    dropped entirely instead of ending the enclosing body early. Parentheses are
+    skipped as a whole too, so the `;` in a for header doesn't end the statement.
+    The block of a do-while is followed by its condition, so skipping goes on to
+    the `;` after it.
+    A statement that is missing its `;` fails at the first token of the next one,
+    so skipping also stops there when that token can only start a statement.
+    */
+    pub fn synchronize_statement(&mut self) {
+        let start_index : usize = self.current_token_index;
+        let mut depth : u32 = 0;
+        let mut paren_depth : u32 = 0;
+        let do_while : bool = self.current_token().val == "do";
+        while !self.at_end() {
+            let val : String = self.current_token().val.clone();
+            if val == "}" && depth == 0 {
+                return;
+            }
+            if depth == 0 && paren_depth == 0 && self.current_token_index > start_index && self.current_token_index >= self.furthest_failure && starts_statement(&val) {
+                return;
+            }
+            self.next_token_index();
+            if val == "(" {
+                paren_depth += 1;
+            }
+            else if val == ")" {
+                paren_depth = paren_depth.saturating_sub(1);
+            }
+            else if val == "{" {
+                //Braces never appear inside parentheses, so a `(` that wasn't closed ends here
+                paren_depth = 0;
+                depth += 1;
+            }
+            else if val == "}" {
+                depth -= 1;
+                if depth == 0 && !do_while {
+                    return;
+                }
+            }
+            else if val == ";" && depth == 0 && paren_depth == 0 {
+                return;
+            }
+        }
+    }
+
+    /* 
+    Panic mode recovery for top level declarations. Tokens are skipped until
+    something that looks like the start of a function, `primitive identifier (`,
+    is found outside of any braces.
+    */
+    pub fn synchronize_declaration(&mut self) {
+        let mut depth : u32 = 0;
+        loop {
+            let val : String = self.current_token().val.clone();
+            self.next_token_index();
+            if val == "{" {
+                depth += 1;
+            }
+            else if val == "}" && depth > 0 {
+                depth -= 1;
+            }
+
+            if 
+            self.at_end() || 
+            (depth == 0 && 
+            self.current_token().token_type == TokenType::Primitive &&
+            self.peek_token(1).token_type == TokenType::Identifier &&
+            self.peek_token(2).val == "(") {
+                return;
+            }
+        }
+    }
+
+    /* 
+    Looks ahead over the whole token stream for the signature of every function
+    declared at the top level, without moving the cursor. Signatures that do not
+    fit the grammar are skipped, the normal parse reports them later.
+    */
+    pub fn scan_function_signatures(&self) -> Vec<FunctionSignature> {
+        let mut signatures : Vec<FunctionSignature> = Vec::new();
+        let mut depth : u32 = 0;
+        let mut index : usize = 0;
+
+        while index < self.tokens.len() {
+            let token : &Token = &self.tokens[index];
+            if token.val == "{" {
+                depth += 1;
+            }
+            else if token.val == "}" && depth > 0 {
+                depth -= 1;
+            }
+            else if depth == 0 && token.token_type == TokenType::Primitive {
+                signatures.extend(self.scan_signature(index));
+            }
+            index += 1;
+        }
+        return signatures;
+    }
+
+    //Reads `primitive identifier (primitive identifier, ...)` starting at index
+    fn scan_signature(&self, index : usize) -> Option<FunctionSignature> {
+        let identifier : &Token = self.tokens.get(index + 1)?;
+        if identifier.token_type != TokenType::Identifier || self.tokens.get(index + 2)?.val != "(" {
+            return Option::None;
+        }
+        let mut params : Vec<String> = Vec::new();
+        let mut current : usize = index + 3;
+
+        if self.tokens.get(current)?.val != ")" {
+            loop {
+                let primitive : &Token = self.tokens.get(current)?;
+                if primitive.token_type != TokenType::Primitive || self.tokens.get(current + 1)?.token_type != TokenType::Identifier {
+                    return Option::None;
+                }
+                params.push(primitive.val.clone());
+                current += 2;
+
+                let separator : &Token = self.tokens.get(current)?;
+                if separator.val == ")" {
+                    break;
+                }
+                else if separator.val != "," {
+                    return Option::None;
+                }
+                current += 1;
+            }
+        }
+
+        return Option::Some(FunctionSignature {
+            identifier : identifier.val.clone(),
+            primitive : self.tokens[index].val.clone(),
+            params,
+            span : identifier.span
+        });
+    }
+
+    /* 
+    Called after a statement or declaration failed to parse. A syntax error is
+    only added if nothing more specific, such as an undeclared identifier, was
+    reported while trying to parse it.
+    */
+    pub fn report_failure(&mut self, errors_before : usize) {
+        if self.error_count() == errors_before {
+            let syntax_error : Diagnostic = self.syntax_error();
+            self.report(syntax_error);
+        }
     }
-    
 }
 
 
 
 
+//Whether a token can only be the first token of a statement
+fn starts_statement(val : &str) -> bool {
+    return is_primitive(val) || matches!(val, "return" | "if" | "while" | "do" | "for" | "switch" | "break" | "continue");
+}
+
 #[allow(non_camel_case_types)]
+#[derive(Clone, Copy)]
 pub enum NodeType {
     
     Program_Start,
@@ -148,31 +459,24 @@ pub enum NodeType {
     Primitive,
     Identifier,
     Body,
+    Block,
     Assign_Expr,
     Expression,
     Optional_Expr,
-    Condition_Expr,
-    Arith_Expr,
-    Arith_Subexpr,
-    Arith_Term,
-    Arith_Subterm,
-    Arith_Factor,
-    Or_Expr,
-    Or_Subexpr,
-    And_Expr,
-    And_Subexpr,
-    Equality_Expr,
-    Equality_Subexpr,
-    Relational_Expr,
-    Relational_Subexpr,
-    Not_Expr,
+    Binary_Expr,
+    Unary_Expr,
+    Postfix_Expr,
     Statement,
     If_Stmt,
     Elif_Stmt,
     Else_Stmt,
     While_Stmt,
     For_Stmt,
+    Do_While_Stmt,
+    Switch_Stmt,
+    Case_Label,
     Return_Stmt,
+    Jump_Stmt,
     VarDecl,
     Keyword,
     Constant,
@@ -182,167 +486,218 @@ pub enum NodeType {
 pub struct Node {
     pub node_type : NodeType,
     pub children : Vec<Node>,
-    pub properties : HashMap<String, String>
+    pub properties : HashMap<String, String>,
+    //The source text this node was parsed from
+    pub span : Span,
+    //Set on nodes that open a new scope, such as functions and loops
+    pub scope : Option<Rc<STNode>>,
+    //Value of a constant, checked and wrapped around to an int while parsing
+    pub constant : Option<i32>
 }
 
 pub fn create_node(n_type : NodeType) -> Node {
     return Node {
         node_type : n_type,
         children : Vec::new(),
-        properties : HashMap::new()
+        properties : HashMap::new(),
+        span : Span::default(),
+        scope : Option::None,
+        constant : Option::None
     };
 } 
 
+//Creates a terminal node that only accepts one specific token, such as a `(` separator or an `if` keyword
+pub fn create_expected_node(n_type : NodeType, expected : &str) -> Node {
+    let mut node : Node = create_node(n_type);
+    node.properties.insert("expected".to_string(), expected.to_string());
+    return node;
+}
 
 
-pub fn parse(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool{
-
-    match current_node.node_type {
-
-        NodeType::Program_Start => parse_start_node(current_node, tokens, symbol_table),
-        
-        NodeType::Other_Decl => parse_other_decl(current_node, tokens, symbol_table),
-
-        NodeType::Func_Call => parse_func_call(current_node, tokens, symbol_table),
-
-        NodeType::Func_Decl => parse_func_decl(current_node, tokens, symbol_table),
-
-        NodeType::Call_Args => parse_call_args(current_node, tokens, symbol_table),
-
-        NodeType::Arguments => parse_arguments(current_node, tokens, symbol_table),
-
-        NodeType::Primitive => parse_terminal(current_node, tokens, &TokenType::Primitive),
 
-        NodeType::Identifier => parse_terminal(current_node, tokens, &TokenType::Identifier),
+pub fn parse(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool{
 
-        NodeType::Separator => parse_terminal(current_node, tokens, &TokenType::Separator),
+    let start_index : usize = parser.current_token_index;
 
-        NodeType::Body => parse_body(current_node, tokens, symbol_table),
+    let parsed : bool = match current_node.node_type {
 
-        NodeType::Assign_Expr => parse_assign_expr(current_node, tokens, symbol_table),
+        NodeType::Program_Start => parse_start_node(current_node, parser, symbol_table),
+        
+        NodeType::Other_Decl => parse_other_decl(current_node, parser, symbol_table),
 
-        NodeType::Expression => parse_expr(current_node, tokens, symbol_table),
+        NodeType::Func_Call => parse_func_call(current_node, parser, symbol_table),
 
-        NodeType::Arith_Expr => parse_arith_expr(current_node, tokens, symbol_table),
+        NodeType::Func_Decl => parse_func_decl(current_node, parser, symbol_table),
 
-        NodeType::Arith_Subexpr => parse_arith_subexpr(current_node, tokens, symbol_table),
+        NodeType::Call_Args => parse_call_args(current_node, parser, symbol_table),
 
-        NodeType::Arith_Term => parse_arith_term(current_node, tokens, symbol_table),
+        NodeType::Arguments => parse_arguments(current_node, parser, symbol_table),
 
-        NodeType::Arith_Subterm => parse_arith_subterm(current_node, tokens, symbol_table),
+        NodeType::Primitive => parse_primitive(current_node, parser),
 
-        NodeType::Arith_Factor => parse_arith_factor(current_node, tokens, symbol_table),
+        NodeType::Identifier => parse_terminal(current_node, parser, &TokenType::Identifier),
 
-        NodeType::Or_Expr => parse_or_epxr(current_node, tokens, symbol_table),
+        NodeType::Separator => parse_terminal(current_node, parser, &TokenType::Separator),
 
-        NodeType::Or_Subexpr => parse_or_subepxr(current_node, tokens, symbol_table),
+        NodeType::Body => parse_body(current_node, parser, symbol_table),
 
-        NodeType::And_Expr => parse_and_expr(current_node, tokens, symbol_table),
+        NodeType::Block => parse_block(current_node, parser, symbol_table),
 
-        NodeType::And_Subexpr => parse_and_subexpr(current_node, tokens, symbol_table),
+        NodeType::Assign_Expr => parse_assign_expr(current_node, parser, symbol_table),
 
-        NodeType::Equality_Expr => parse_equality_expr(current_node, tokens, symbol_table),
+        NodeType::Expression => parse_expr(current_node, parser, symbol_table),
 
-        NodeType::Equality_Subexpr => parse_equality_subexpr(current_node, tokens, symbol_table),
+        //Operator nodes are assembled by the precedence climbing in parse_expr rather than parsed on their own
+        NodeType::Binary_Expr | NodeType::Unary_Expr | NodeType::Postfix_Expr => parse_expr(current_node, parser, symbol_table),
 
-        NodeType::Relational_Expr => parse_relational_expr(current_node, tokens, symbol_table),
+        NodeType::Optional_Expr => parse_optional_expr(current_node, parser, symbol_table),
 
-        NodeType::Relational_Subexpr => parse_relational_subexpr(current_node, tokens, symbol_table),
+        NodeType::Statement => parse_statement(current_node, parser, symbol_table),
 
-        NodeType::Not_Expr => parse_not_expr(current_node, tokens, symbol_table),
+        NodeType::VarDecl => parse_var_decl(current_node, parser, symbol_table),
 
-        NodeType::Condition_Expr => parse_cond_expr(current_node, tokens, symbol_table),
+        NodeType::Return_Stmt => parse_ret_stmt(current_node, parser, symbol_table),
 
-        NodeType::Optional_Expr => parse_optional_expr(current_node, tokens, symbol_table),
+        NodeType::Jump_Stmt => parse_jump_stmt(current_node, parser, symbol_table),
 
-        NodeType::Statement => parse_statement(current_node, tokens, symbol_table),
+        NodeType::If_Stmt => parse_if_stmt(current_node, parser, symbol_table),
 
-        NodeType::VarDecl => parse_var_decl(current_node, tokens, symbol_table),
+        NodeType::Elif_Stmt => parse_elif_stmt(current_node, parser, symbol_table),
 
-        NodeType::Return_Stmt => parse_ret_stmt(current_node, tokens, symbol_table),
+        NodeType::Else_Stmt => parse_else_stmt(current_node, parser, symbol_table),
 
-        NodeType::If_Stmt => parse_if_stmt(current_node, tokens, symbol_table),
+        NodeType::While_Stmt => parse_while_stmt(current_node, parser, symbol_table),
 
-        NodeType::Elif_Stmt => parse_elif_stmt(current_node, tokens, symbol_table),
+        NodeType::For_Stmt => parse_for_stmt(current_node, parser, symbol_table),
 
-        NodeType::Else_Stmt => parse_else_stmt(current_node, tokens, symbol_table),
+        NodeType::Do_While_Stmt => parse_do_while_stmt(current_node, parser, symbol_table),
 
-        NodeType::While_Stmt => parse_while_stmt(current_node, tokens, symbol_table),
+        NodeType::Switch_Stmt => parse_switch_stmt(current_node, parser, symbol_table),
 
-        NodeType::For_Stmt => parse_for_stmt(current_node, tokens, symbol_table),
+        NodeType::Case_Label => parse_case_label(current_node, parser, symbol_table),
 
-        NodeType::Keyword => parse_terminal(current_node, tokens, &TokenType::Keyword),
+        NodeType::Keyword => parse_terminal(current_node, parser, &TokenType::Keyword),
 
-        NodeType::Operator => parse_terminal(current_node, tokens, &TokenType::Operator),
+        NodeType::Operator => parse_terminal(current_node, parser, &TokenType::Operator),
 
-        NodeType::Constant => parse_terminal(current_node, tokens, &TokenType::Constant)
+        NodeType::Constant => parse_terminal(current_node, parser, &TokenType::Constant)
     
+    };
+
+    //Every node records the tokens it was built from
+    if parsed {
+        current_node.span = parser.span_from(start_index);
     }
+    return parsed;
 
+}
 
+/* 
+Tries a single alternative of a production. If the alternative fails part of the
+way through, the cursor is rewound to where it started so another alternative
+can be tried.
+*/
+pub fn try_parse(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
+    parser.save_checkpoint();
+    if parse(current_node, parser, symbol_table) {
+        parser.discard_checkpoint();
+        return true;
+    }
+    parser.restore_checkpoint();
+    return false;
 }
 
-fn parse_start_node(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
+fn parse_start_node(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
     //Create a new node of type function declaration            
     let mut other_decl_node : Node = create_node(NodeType::Other_Decl);
 
     
+    //Every function is known before any body is parsed, so functions can call each other regardless of order
+    for signature in parser.scan_function_signatures() {
+        if symbol_table.get_table().query(&signature.identifier).is_none() {
+            symbol_table.bind_function(&signature.identifier, &signature.primitive, &signature.params, false, signature.span);
+        }
+    }
+
     /* 
     Parse the function declaration node first. If it returns a node, then
     the parser is free to continue, if it returns None, then the parser
     must backtrack.
     */
 
-    if parse(&mut other_decl_node, tokens, symbol_table) {
+    if parse(&mut other_decl_node, parser, symbol_table) {
         current_node.children.push(other_decl_node);
         return true;
     }
     return false;
 }
 
-fn parse_func_decl(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
+fn parse_func_decl(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
     //New scope made here
 
-    symbol_table.push_child(1);
+    symbol_table.push_child();
     let current_table = &symbol_table.children.borrow()[symbol_table.children.borrow().len() - 1];
+    current_node.scope = Option::Some(current_table.clone());
 
 
     let mut primitive_node : Node = create_node(NodeType::Primitive);
     let mut identifier_node : Node = create_node(NodeType::Identifier);
-    let mut open_paren_node : Node = create_node(NodeType::Separator);
+    let mut open_paren_node : Node = create_expected_node(NodeType::Separator, "(");
     let mut arguments_node : Node = create_node(NodeType::Arguments);
-    let mut close_paren_node : Node = create_node(NodeType::Separator);
-    let mut open_curly_node : Node = create_node(NodeType::Separator);
+    let mut close_paren_node : Node = create_expected_node(NodeType::Separator, ")");
+    let mut open_curly_node : Node = create_expected_node(NodeType::Separator, "{");
     let mut body_node : Node = create_node(NodeType::Body);
-    let mut close_curly_node : Node = create_node(NodeType::Separator);
+    let mut close_curly_node : Node = create_expected_node(NodeType::Separator, "}");
 
-    arguments_node.properties.insert("current_arg".to_string(), (-2).to_string());
+    arguments_node.properties.insert("current_arg".to_string(), 0.to_string());
+
+    //The return type has to be known before the body is parsed, so it is handled on its own
+    if !parse(&mut primitive_node, parser, current_table) {
+        return false;
+    }
     body_node.properties.insert("return_type".to_string(), primitive_node.properties["value"].clone());
 
+    if !(
+    parse(&mut identifier_node, parser, current_table) &&
+    parse(&mut open_paren_node, parser, current_table) &&
+    parse(&mut arguments_node, parser, current_table) &&
+    parse(&mut close_paren_node, parser, current_table)) {
+        return false;
+    }
+    let params : Vec<String> = argument_primitives(&arguments_node);
+
+    current_node.properties.insert("arguments".to_string(), arguments_node.properties["arguments"].clone());
+    current_node.children.push(primitive_node);
+    current_node.children.push(identifier_node);
+    current_node.children.push(open_paren_node);
+    current_node.children.push(arguments_node);
+    current_node.children.push(close_paren_node);
+
+    if parser.current_token().val == ";" {
+        //A prototype only makes the function known, its body comes later in the file
+        let mut semicolon_node : Node = create_expected_node(NodeType::Separator, ";");
+        if parse(&mut semicolon_node, parser, current_table) {
+            declare_function(parser, symbol_table, &current_node.children[1], &current_node.children[0], &params, false);
+            current_node.properties.insert("prototype".to_string(), "true".to_string());
+            current_node.children.push(semicolon_node);
+            return true;
+        }
+        return false;
+    }
+
+    if !parse(&mut open_curly_node, parser, current_table) {
+        return false;
+    }
+    //Declared before the body is parsed so that the function can call itself
+    declare_function(parser, symbol_table, &current_node.children[1], &current_node.children[0], &params, true);
+
     if 
-    parse(&mut primitive_node, tokens, current_table) &&
-    parse(&mut identifier_node, tokens, current_table) &&
-    parse(&mut open_paren_node, tokens, current_table) &&
-    parse(&mut arguments_node, tokens, current_table) &&
-    parse(&mut close_paren_node, tokens, current_table) &&
-    parse(&mut open_curly_node, tokens, current_table) &&
-    parse(&mut body_node, tokens, current_table) &&
-    parse(&mut close_curly_node, tokens, current_table)
+    parse(&mut body_node, parser, current_table) &&
+    parse(&mut close_curly_node, parser, current_table)
     {
-        let args : u32 = arguments_node.properties["arguments"].parse::<u32>().unwrap();
-        symbol_table.bind(&identifier_node.properties["value"], &primitive_node.properties["value"], args, true);
-        current_node.properties.insert("var_alloc".to_string(), body_node.properties["var_alloc"].clone());
-        current_node.properties.insert("arguments".to_string(), arguments_node.properties["arguments"].clone());
-        current_node.children.push(primitive_node);
-        current_node.children.push(identifier_node);
-        current_node.children.push(open_paren_node);
-        current_node.children.push(arguments_node);
-        current_node.children.push(close_paren_node);
         current_node.children.push(open_curly_node);
         current_node.children.push(body_node);
         current_node.children.push(close_curly_node);
-
-        
         
         return true;
     }
@@ -351,26 +706,115 @@ fn parse_func_decl(current_node : &mut Node, tokens : &Vec<Token>, symbol_table
     }
 }
 
-fn parse_terminal(current_node : &mut Node, tokens : &Vec<Token>, tok_type : &TokenType) -> bool {
+//Collects the primitive of every parameter in an Arguments chain
+fn argument_primitives(arguments_node : &Node) -> Vec<String> {
+    let mut params : Vec<String> = Vec::new();
+    let mut current : &Node = arguments_node;
+    while current.properties.contains_key("primitive") {
+        params.push(current.properties["primitive"].clone());
+        if current.children.len() < 4 {
+            break;
+        }
+        current = &current.children[3];
+    }
+    return params;
+}
+
+/* 
+Binds a function in the global scope. When the function was already declared,
+the new declaration has to agree with the old one on the return type and the
+parameter types, and only one of them may come with a body.
+*/
+fn declare_function(parser : &mut Parser, symbol_table : &Rc<STNode>, identifier_node : &Node, primitive_node : &Node, params : &Vec<String>, defined : bool) {
+    let identifier : &String = &identifier_node.properties["value"];
+    let primitive : &String = &primitive_node.properties["value"];
+    let previous : Option<Symbol> = symbol_table.get_table().query(identifier).cloned();
+
+    if let Option::Some(previous) = previous.filter(|symbol| symbol.func) {
+        if defined && previous.defined {
+            parser.report(
+                Diagnostic::error("E0005", format!("redefinition of function `{}`", identifier), identifier_node.span, "redefined here".to_string())
+                .with_secondary(previous.span, "previous definition here".to_string())
+            );
+            return;
+        }
+        if &previous.primitive != primitive || &previous.params != params {
+            parser.report(
+                Diagnostic::error("E0004", format!("conflicting types for `{}`", identifier), identifier_node.span, "does not match the previous declaration".to_string())
+                .with_secondary(previous.span, "previous declaration here".to_string())
+                .with_note(format!("previously declared as `{}`, now declared as `{}`", signature(identifier, &previous.primitive, &previous.params), signature(identifier, primitive, params)))
+            );
+            return;
+        }
+        if !defined {
+            //Repeating a prototype changes nothing, and must not forget an earlier definition
+            return;
+        }
+    }
+    symbol_table.bind_function(identifier, primitive, params, defined, identifier_node.span);
+}
+
+fn signature(identifier : &str, primitive : &str, params : &[String]) -> String {
+    return format!("{} {}({})", primitive, identifier, params.join(", "));
+}
+
+fn parse_terminal(current_node : &mut Node, parser : &mut Parser, tok_type : &TokenType) -> bool {
 
-    if tok_type == &tokens[get_current_token_index()].token_type {
-        current_node.properties.insert("value".to_string(), (&tokens[get_current_token_index()].val).clone());
-        next_token_index();
+    if 
+    current_node.properties.contains_key("expected") &&
+    current_node.properties["expected"] != parser.current_token().val {
+        parser.expect(format!("`{}`", current_node.properties["expected"]));
+        return false;
+    }
+
+    if tok_type == &parser.current_token().token_type {
+        current_node.properties.insert("value".to_string(), parser.current_token().val.clone());
+        parser.next_token_index();
         return true;
     }
+    parser.expect(token_type_name(tok_type));
     return false;
     
 }
 
+/* 
+Parses a type. A type the code generator can't handle yet is reported where it is
+written, and parsing carries on as if it were supported so its uses don't cause
+more errors.
+*/
+fn parse_primitive(current_node : &mut Node, parser : &mut Parser) -> bool {
+    let span : Span = parser.current_token().span;
+    if !parse_terminal(current_node, parser, &TokenType::Primitive) {
+        return false;
+    }
+    let primitive : String = current_node.properties["value"].clone();
+    if !SUPPORTED_PRIMITIVES.contains(&primitive.as_str()) {
+        parser.report(Diagnostic::error("E0012", format!("unsupported type `{}`", primitive), span, "this type can't be compiled yet".to_string()));
+    }
+    return true;
+}
 
-fn parse_arguments(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
+fn token_type_name(tok_type : &TokenType) -> String {
+    return match tok_type {
+        TokenType::Identifier => "an identifier",
+        TokenType::Separator => "a separator",
+        TokenType::Keyword => "a keyword",
+        TokenType::Primitive => "a type",
+        TokenType::Operator => "an operator",
+        TokenType::Constant => "a constant",
+        TokenType::Default => "a token"
+    }.to_string();
+}
+
+
+fn parse_arguments(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
 
     let mut prim_node : Node = create_node(NodeType::Primitive);
     let mut identifier_node : Node = create_node(NodeType::Identifier);
-    let mut separator_node : Node = create_node(NodeType::Separator);
+    let mut separator_node : Node = create_expected_node(NodeType::Separator, ",");
     let mut argument_node : Node = create_node(NodeType::Arguments);
 
-    if tokens[get_current_token_index()].val == ")" {
+    if parser.current_token().val == ")" {
         current_node.properties.insert("arguments".to_string(), "0".to_string());
         return true;
     }
@@ -379,24 +823,24 @@ fn parse_arguments(current_node : &mut Node, tokens : &Vec<Token>, symbol_table
     argument_node.properties.insert("current_arg".to_string(), (current_arg + 1).to_string());
 
     if
-    parse(&mut prim_node, tokens, symbol_table) &&
-    parse(&mut identifier_node, tokens, symbol_table) {
+    parse(&mut prim_node, parser, symbol_table) &&
+    parse(&mut identifier_node, parser, symbol_table) {
 
         current_node.properties.insert("primitive".to_string(), prim_node.properties["value"].clone());
         current_node.properties.insert("identifier".to_string(), identifier_node.properties["value"].clone());
-        symbol_table.bind_arg(&identifier_node.properties["value"], &prim_node.properties["value"], current_arg * -1);
+        symbol_table.bind_arg(&identifier_node.properties["value"], &prim_node.properties["value"], current_arg, identifier_node.span);
         current_node.children.push(prim_node);
         current_node.children.push(identifier_node);
 
         if 
-        tokens[get_current_token_index()].val == ")" {
+        parser.current_token().val == ")" {
             current_node.children.push(separator_node);
             current_node.properties.insert("arguments".to_string(), "1".to_string());
             return true;
         }
         if 
-        parse(&mut separator_node, tokens, symbol_table) &&
-        parse(&mut argument_node, tokens, symbol_table) {
+        parse(&mut separator_node, parser, symbol_table) &&
+        parse(&mut argument_node, parser, symbol_table) {
             current_node.children.push(separator_node);
             //We propagate the number of arguments upwards so that later we know how much space to allocate on stack.
             let arg_num: i32 = argument_node.properties["arguments"].clone().parse::<i32>().unwrap() + 1;
@@ -412,21 +856,29 @@ fn parse_arguments(current_node : &mut Node, tokens : &Vec<Token>, symbol_table
     return false;
 }
 
-fn parse_other_decl(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
+fn parse_other_decl(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
 
     let mut func_decl_node: Node = create_node(NodeType::Func_Decl);
     let mut other_decl_node : Node = create_node(NodeType::Other_Decl);
 
-    if get_current_token_index() == tokens.len() {
+    if parser.at_end() {
         return true;
     }
-    else if 
-    parse(&mut func_decl_node, tokens, symbol_table) &&
-    parse(&mut other_decl_node, tokens, symbol_table) {
 
+    let errors_before : usize = parser.error_count();
+    parser.reset_expected();
+
+    if try_parse(&mut func_decl_node, parser, symbol_table) {
         current_node.children.push(func_decl_node);
-        current_node.children.push(other_decl_node);
+    }
+    else {
+        //Report what went wrong and carry on from the next function so every broken declaration is reported
+        parser.report_failure(errors_before);
+        parser.synchronize_declaration();
+    }
 
+    if parse(&mut other_decl_node, parser, symbol_table) {
+        current_node.children.push(other_decl_node);
         return true;
     }
 
@@ -1,59 +1,66 @@
 /* This file will contain the code necessary to parse statements or 
 bodies of statements */
 use std::rc::Rc;
-use crate::{parse_c::{ create_node, get_current_token_index,  parse, Node, NodeType}, token_c::{is_identifier, is_primitive, Token}};
+use crate::{parse_c::{ create_node, create_expected_node, parse, try_parse, Node, NodeType, Parser}, token_c::{is_identifier, is_primitive, Span}};
 use crate::symbol_table_c::{*};
+use crate::diagnostic_c::Diagnostic;
 
-pub fn parse_statement(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) ->bool {
+pub fn parse_statement(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) ->bool {
     /* Include all rules for CFGs that have statements on the LHS here. */
-    if tokens[get_current_token_index()].val == "return".to_string() {
+    if parser.current_token().val == "return" {
         let mut return_node : Node = create_node(NodeType::Return_Stmt);
 
-        if parse(&mut return_node, tokens, symbol_table) 
+        if parse(&mut return_node, parser, symbol_table) 
         {
             current_node.children.push(return_node);
             return true;
         }
     }
     else if 
-    is_primitive(&tokens[get_current_token_index()].val) {
+    is_primitive(&parser.current_token().val) {
         //Then we have found a variable declaration
         let mut var_decl : Node = create_node(NodeType::VarDecl);
-        let mut semicolon_node : Node = create_node(NodeType::Separator);
+        let mut semicolon_node : Node = create_expected_node(NodeType::Separator, ";");
 
         if 
-        parse(&mut var_decl, tokens, symbol_table) &&
-        parse(&mut semicolon_node, tokens, symbol_table)
+        parse(&mut var_decl, parser, symbol_table) &&
+        parse(&mut semicolon_node, parser, symbol_table)
         {
             current_node.children.push(var_decl);
-            current_node.properties.insert("var_alloc".to_string(), "1".to_string());
             return true;
         }
         
     }
     else if 
-    is_identifier(&tokens[get_current_token_index()].val) {
+    is_identifier(&parser.current_token().val) ||
+    parser.current_token().val == "++" ||
+    parser.current_token().val == "--" {
         /* 
-        Then we have either found an assignment expression OR we have found a 
-        function call.
+        Then we have either found a function call OR an expression such as an
+        assignment or an increment.
         */
-        let mut assign_expr : Node = create_node(NodeType::Assign_Expr);
+        let mut expr_node : Node = create_node(NodeType::Expression);
         let mut func_call_node : Node = create_node(NodeType::Func_Call);
-        let mut semicolon_node : Node = create_node(NodeType::Separator);
+        let mut semicolon_node : Node = create_expected_node(NodeType::Separator, ";");
 
+        parser.save_checkpoint();
         if
-        tokens[get_current_token_index() + 1].val == "(" &&
-        parse(&mut func_call_node, tokens, symbol_table) &&
-        parse(&mut semicolon_node, tokens, symbol_table) {
+        parser.peek_token(1).val == "(" &&
+        parse(&mut func_call_node, parser, symbol_table) &&
+        parse(&mut semicolon_node, parser, symbol_table) {
             //Then this must be a function call
+            parser.discard_checkpoint();
             current_node.children.push(func_call_node);
             current_node.children.push(semicolon_node);
             return true;
         }
-        else if parse(&mut assign_expr, tokens, symbol_table) &&
-        parse(&mut semicolon_node, tokens, symbol_table){
-            //Otherwise this must be an assignment
-            current_node.children.push(assign_expr);
+        parser.restore_checkpoint();
+
+        let mut semicolon_node : Node = create_expected_node(NodeType::Separator, ";");
+        if parse(&mut expr_node, parser, symbol_table) &&
+        parse(&mut semicolon_node, parser, symbol_table){
+            //Otherwise this must be an expression evaluated for its side effects
+            current_node.children.push(expr_node);
             current_node.children.push(semicolon_node);
             return true;
         }
@@ -61,56 +68,90 @@ pub fn parse_statement(current_node : &mut Node, tokens : &Vec<Token>, symbol_ta
         return false;
         
     }
-    else if tokens[get_current_token_index()].val == "if".to_string() {
+    else if parser.current_token().val == "break" || parser.current_token().val == "continue" {
+        let mut jump_stmt : Node = create_node(NodeType::Jump_Stmt);
+        if parse(&mut jump_stmt, parser, symbol_table) {
+            current_node.children.push(jump_stmt);
+            return true;
+        }
+    }
+    else if parser.current_token().val == "if" {
         let mut if_stmt : Node = create_node(NodeType::If_Stmt);
-        if parse(&mut if_stmt, tokens, symbol_table) 
+        if parse(&mut if_stmt, parser, symbol_table) 
         {
             current_node.children.push(if_stmt);
             return true;
         }
     }
-    else if tokens[get_current_token_index()].val == "for".to_string() {
+    else if parser.current_token().val == "for" {
         let mut for_stmt : Node = create_node(NodeType::For_Stmt);
-        if parse(&mut for_stmt, tokens, symbol_table) 
+        if parse(&mut for_stmt, parser, symbol_table) 
         {
             current_node.children.push(for_stmt);
             return true;
         }
     }
-    else if tokens[get_current_token_index()].val == "while".to_string() {
+    else if parser.current_token().val == "while" {
         let mut while_stmt : Node = create_node(NodeType::While_Stmt);
-        if parse(&mut while_stmt, tokens, symbol_table) 
+        if parse(&mut while_stmt, parser, symbol_table) 
         {
             current_node.children.push(while_stmt);
             return true;
         }
     }
+    else if parser.current_token().val == "do" {
+        let mut do_while_stmt : Node = create_node(NodeType::Do_While_Stmt);
+        if parse(&mut do_while_stmt, parser, symbol_table) {
+            current_node.children.push(do_while_stmt);
+            return true;
+        }
+    }
+    else if parser.current_token().val == "switch" {
+        let mut switch_stmt : Node = create_node(NodeType::Switch_Stmt);
+        if parse(&mut switch_stmt, parser, symbol_table) {
+            current_node.children.push(switch_stmt);
+            return true;
+        }
+    }
+    else if is_case_keyword(&parser.current_token().val) {
+        //Labels are only parsed as part of a switch body, so one found here is somewhere else
+        let keyword : String = parser.current_token().val.clone();
+        parser.report(Diagnostic::error("E0008", format!("`{}` label outside of a switch body", keyword), parser.current_token().span, "not directly inside a switch".to_string()));
+    }
+    else {
+        parser.expect("a statement".to_string());
+    }
     return false;
 }
 
-pub fn parse_var_decl(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) ->bool {
+pub fn parse_var_decl(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) ->bool {
     let mut primitive_node : Node = create_node(NodeType::Primitive);
     let mut identity_node : Node = create_node(NodeType::Identifier);
     
     
 
-    if parse(&mut primitive_node, tokens, symbol_table) {
+    if parse(&mut primitive_node, parser, symbol_table) {
         
         let mut expr_node : Node = create_node(NodeType::Assign_Expr);
         current_node.children.push(primitive_node);
 
-        if is_identifier(&tokens[get_current_token_index()].val) {
-            symbol_table.bind(&tokens[get_current_token_index()].val, &current_node.children[0].properties["value"], 0,false);
+        if is_identifier(&parser.current_token().val) {
+            symbol_table.bind(&parser.current_token().val, &current_node.children[0].properties["value"], 0,false, parser.current_token().span);
         }
 
-        if parse(&mut expr_node, tokens, symbol_table) {
+        if parse(&mut expr_node, parser, symbol_table) {
+            //Only a plain `=` can give a declaration its first value
+            let operator_node : &Node = &expr_node.children[1];
+            if operator_node.properties["value"] != "=" {
+                parser.report(Diagnostic::error("E0001", format!("expected `=`, found `{}`", operator_node.properties["value"]), operator_node.span, "expected `=`".to_string()));
+            }
             current_node.children.push(expr_node);
 
             current_node.properties.insert("identifier".to_string(), current_node.children[1].properties["identifier"].clone());
             return true;
 
         }
-        else if parse(&mut identity_node, tokens, symbol_table) {
+        else if parse(&mut identity_node, parser, symbol_table) {
             current_node.children.push(identity_node);
 
             current_node.properties.insert("value".to_string(), "0".to_string());
@@ -127,17 +168,47 @@ pub fn parse_var_decl(current_node : &mut Node, tokens : &Vec<Token>, symbol_tab
     return false;
 }
 
-pub fn parse_ret_stmt(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) ->bool {
-    let mut return_node : Node = create_node(NodeType::Keyword);
-    let mut expr_node : Node = create_node(NodeType::Arith_Expr);
-    let mut semicolon_node : Node = create_node(NodeType::Separator);
+pub fn parse_jump_stmt(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
+    let keyword : String = parser.current_token().val.clone();
+    let mut keyword_node : Node = create_expected_node(NodeType::Keyword, keyword.as_str());
+    let mut semicolon_node : Node = create_expected_node(NodeType::Separator, ";");
+
+    if
+    parse(&mut keyword_node, parser, symbol_table) &&
+    parse(&mut semicolon_node, parser, symbol_table) {
+        if keyword == "break" && parser.loop_depth == 0 && parser.switch_depth == 0 {
+            parser.report(Diagnostic::error("E0007", "`break` outside of a loop or switch".to_string(), keyword_node.span, "cannot `break` outside of a loop or switch".to_string()));
+        }
+        else if keyword == "continue" && parser.loop_depth == 0 {
+            parser.report(Diagnostic::error("E0007", "`continue` outside of a loop".to_string(), keyword_node.span, "cannot `continue` outside of a loop".to_string()));
+        }
+        current_node.properties.insert("keyword".to_string(), keyword);
+        current_node.children.push(keyword_node);
+        current_node.children.push(semicolon_node);
+        return true;
+    }
+    return false;
+}
+
+//Parses the body of a loop, inside which break and continue may be used
+fn parse_loop_body(body_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
+    parser.loop_depth += 1;
+    let parsed : bool = parse(body_node, parser, symbol_table);
+    parser.loop_depth -= 1;
+    return parsed;
+}
+
+pub fn parse_ret_stmt(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) ->bool {
+    let mut return_node : Node = create_expected_node(NodeType::Keyword, "return");
+    let mut expr_node : Node = create_node(NodeType::Expression);
+    let mut semicolon_node : Node = create_expected_node(NodeType::Separator, ";");
 
 
 
     if
-    parse(&mut return_node, tokens, symbol_table) &&
-    parse(&mut expr_node, tokens, symbol_table) &&
-    parse(&mut semicolon_node, tokens, symbol_table) {
+    parse(&mut return_node, parser, symbol_table) &&
+    parse(&mut expr_node, parser, symbol_table) &&
+    parse(&mut semicolon_node, parser, symbol_table) {
         
         current_node.children.push(return_node);
         current_node.children.push(expr_node);
@@ -152,38 +223,84 @@ pub fn parse_ret_stmt(current_node : &mut Node, tokens : &Vec<Token>, symbol_tab
    
 }
 
-pub fn parse_body(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) ->bool {
-    let mut var_alloc : u32 = 0;
-    while tokens[get_current_token_index()].val != "}".to_string(){
+pub fn parse_body(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) ->bool {
+    //The body of a case label ends where the next label starts
+    let case_body : bool = current_node.properties.contains_key("case_body");
+    while parser.current_token().val != "}" && !parser.at_end() {
+        if case_body && is_case_keyword(&parser.current_token().val) {
+            break;
+        }
         let mut stmt_node : Node = create_node(NodeType::Statement);
 
         if current_node.properties.contains_key("return_type") {
             stmt_node.properties.insert("return_type".to_string(), current_node.properties["return_type"].clone());
         }
+
+        let errors_before : usize = parser.error_count();
+        parser.reset_expected();
         
-        if parse(&mut stmt_node, tokens, symbol_table) {
-            if stmt_node.properties.contains_key("var_alloc") {
-                var_alloc += 1;
-            }
+        if try_parse(&mut stmt_node, parser, symbol_table) {
             current_node.children.push(stmt_node);
         }
         else {
-            return false;
+            //Report the broken statement and skip past it so the rest of the body is still checked
+            parser.report_failure(errors_before);
+            parser.synchronize_statement();
         }
 
     }
-    current_node.properties.insert("var_alloc".to_string(), var_alloc.to_string());
     return true;
 }
 
 
 
-pub fn parse_if_stmt(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
+/* 
+The body of an if, elif, else, while or for statement. C allows a single
+statement in place of a block, which is given a body of its own so that it is
+handled just like a block holding only that statement.
+*/
+pub fn parse_block(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
+    if parser.current_token().val != "{" {
+        let mut body_node : Node = create_node(NodeType::Body);
+        let mut stmt_node : Node = create_node(NodeType::Statement);
+
+        let errors_before : usize = parser.error_count();
+        parser.reset_expected();
+
+        if try_parse(&mut stmt_node, parser, symbol_table) {
+            body_node.span = stmt_node.span;
+            body_node.children.push(stmt_node);
+        }
+        else {
+            //Recovered from the same way as a statement in braces, so the statement around it is kept
+            parser.report_failure(errors_before);
+            parser.synchronize_statement();
+        }
+        current_node.children.push(body_node);
+        return true;
+    }
+
+    let mut open_curly_node : Node = create_expected_node(NodeType::Separator, "{");
+    let mut body_node : Node = create_node(NodeType::Body);
+    let mut close_curly_node : Node = create_expected_node(NodeType::Separator, "}");
+    if
+    parse(&mut open_curly_node, parser, symbol_table) &&
+    parse(&mut body_node, parser, symbol_table) &&
+    parse(&mut close_curly_node, parser, symbol_table) {
+        current_node.children.push(open_curly_node);
+        current_node.children.push(body_node);
+        current_node.children.push(close_curly_node);
+        return true;
+    }
+    return false;
+}
+
+pub fn parse_if_stmt(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
     let mut elif_stmt_node : Node = create_node(NodeType::Elif_Stmt);
 
     if 
-    handle_if_block(current_node, tokens, symbol_table) &&
-    parse(&mut elif_stmt_node, tokens, symbol_table) {
+    handle_if_block(current_node, parser, symbol_table) &&
+    parse(&mut elif_stmt_node, parser, symbol_table) {
 
         current_node.children.push(elif_stmt_node);
         return true;
@@ -193,32 +310,27 @@ pub fn parse_if_stmt(current_node : &mut Node, tokens : &Vec<Token>, symbol_tabl
     return false;
 }
 
-fn handle_if_block(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-    symbol_table.push_child(symbol_table.get_ordinal());
+fn handle_if_block(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
+    symbol_table.push_child();
     let current_table = &symbol_table.children.borrow()[symbol_table.children.borrow().len() - 1];
-    let mut keyword_node : Node = create_node(NodeType::Keyword);
-    let mut open_paren_node : Node = create_node(NodeType::Separator);
-    let mut cond_node : Node = create_node(NodeType::Condition_Expr);
-    let mut close_paren_node : Node = create_node(NodeType::Separator);
-    let mut open_curly_node : Node = create_node(NodeType::Separator);
-    let mut body_node : Node = create_node(NodeType::Body);
-    let mut close_curly_node : Node = create_node(NodeType::Separator);
+    current_node.scope = Option::Some(current_table.clone());
+    let mut keyword_node : Node = create_expected_node(NodeType::Keyword, "if");
+    let mut open_paren_node : Node = create_expected_node(NodeType::Separator, "(");
+    let mut cond_node : Node = create_node(NodeType::Expression);
+    let mut close_paren_node : Node = create_expected_node(NodeType::Separator, ")");
+    let mut block_node : Node = create_node(NodeType::Block);
     if 
-    parse(&mut keyword_node, tokens, current_table) &&
-    parse(&mut open_paren_node, tokens, current_table) &&
-    parse(&mut cond_node, tokens, current_table) &&
-    parse(&mut close_paren_node, tokens, current_table) &&
-    parse(&mut open_curly_node, tokens, current_table) &&
-    parse(&mut body_node, tokens, current_table) &&
-    parse(&mut close_curly_node, tokens, current_table) {
+    parse(&mut keyword_node, parser, current_table) &&
+    parse(&mut open_paren_node, parser, current_table) &&
+    parse(&mut cond_node, parser, current_table) &&
+    parse(&mut close_paren_node, parser, current_table) &&
+    parse(&mut block_node, parser, current_table) {
 
         current_node.children.push(keyword_node);
         current_node.children.push(open_paren_node);
         current_node.children.push(cond_node);
         current_node.children.push(close_paren_node);
-        current_node.children.push(open_curly_node);
-        current_node.children.push(body_node);
-        current_node.children.push(close_curly_node);
+        current_node.children.push(block_node);
 
         return true;
     }
@@ -226,12 +338,12 @@ fn handle_if_block(current_node : &mut Node, tokens : &Vec<Token>, symbol_table
     return false;
 }
 
-pub fn parse_elif_stmt(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
+pub fn parse_elif_stmt(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
     //New scope made here
-    if tokens[get_current_token_index()].val == "else".to_string() {
+    if parser.current_token().val == "else" && parser.peek_token(1).val != "if" {
         let mut else_stmt_node : Node = create_node(NodeType::Else_Stmt);
 
-        if parse(&mut else_stmt_node, tokens, symbol_table) {
+        if parse(&mut else_stmt_node, parser, symbol_table) {
             current_node.children.push(else_stmt_node);
             return true;
         }
@@ -241,13 +353,13 @@ pub fn parse_elif_stmt(current_node : &mut Node, tokens : &Vec<Token>, symbol_ta
     
 
 
-    if tokens[get_current_token_index()].val == "elif".to_string() {
+    if parser.current_token().val == "elif" || parser.current_token().val == "else" {
         
         let mut elif_stmt_node : Node = create_node(NodeType::Elif_Stmt);
         
         if 
-        handle_elif_block(current_node, tokens, symbol_table) &&
-        parse(&mut elif_stmt_node, tokens, symbol_table) {
+        handle_elif_block(current_node, parser, symbol_table) &&
+        parse(&mut elif_stmt_node, parser, symbol_table) {
 
             
             current_node.children.push(elif_stmt_node);
@@ -261,60 +373,68 @@ pub fn parse_elif_stmt(current_node : &mut Node, tokens : &Vec<Token>, symbol_ta
     return true;
 }
 
-fn handle_elif_block(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
-    let mut keyword_node : Node = create_node(NodeType::Keyword);
-    let mut open_paren_node : Node = create_node(NodeType::Separator);
-    let mut cond_node : Node = create_node(NodeType::Condition_Expr);
-    let mut close_paren_node : Node = create_node(NodeType::Separator);
-    let mut open_curly_node : Node = create_node(NodeType::Separator);
-    let mut body_node : Node = create_node(NodeType::Body);
-    let mut close_curly_node : Node = create_node(NodeType::Separator);
+fn handle_elif_block(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
+    let mut keyword_node : Node = create_expected_node(NodeType::Keyword, "elif");
+    let mut open_paren_node : Node = create_expected_node(NodeType::Separator, "(");
+    let mut cond_node : Node = create_node(NodeType::Expression);
+    let mut close_paren_node : Node = create_expected_node(NodeType::Separator, ")");
+    let mut block_node : Node = create_node(NodeType::Block);
 
-    symbol_table.push_child(symbol_table.get_ordinal());
+    symbol_table.push_child();
     let current_table = &symbol_table.children.borrow()[symbol_table.children.borrow().len() - 1];
+    current_node.scope = Option::Some(current_table.clone());
 
     if
-    parse(&mut keyword_node, tokens, current_table) &&
-    parse(&mut open_paren_node, tokens, current_table) &&
-    parse(&mut cond_node, tokens, current_table) &&
-    parse(&mut close_paren_node, tokens, current_table) &&
-    parse(&mut open_curly_node, tokens, current_table) &&
-    parse(&mut body_node, tokens, current_table) &&
-    parse(&mut close_curly_node, tokens, current_table) {
+    parse_elif_keyword(&mut keyword_node, parser, current_table) &&
+    parse(&mut open_paren_node, parser, current_table) &&
+    parse(&mut cond_node, parser, current_table) &&
+    parse(&mut close_paren_node, parser, current_table) &&
+    parse(&mut block_node, parser, current_table) {
         current_node.children.push(keyword_node);
         current_node.children.push(open_paren_node);
         current_node.children.push(cond_node);
         current_node.children.push(close_paren_node);
-        current_node.children.push(open_curly_node);
-        current_node.children.push(body_node);
-        current_node.children.push(close_curly_node);
+        current_node.children.push(block_node);
+        return true;
+    }
+    return false;
+}
+
+//`else if` takes two keywords to say what `elif` does, so both become a single keyword node
+fn parse_elif_keyword(keyword_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
+    if parser.current_token().val != "else" {
+        return parse(keyword_node, parser, symbol_table);
+    }
+
+    let mut else_node : Node = create_expected_node(NodeType::Keyword, "else");
+    let mut if_node : Node = create_expected_node(NodeType::Keyword, "if");
+    if
+    parse(&mut else_node, parser, symbol_table) &&
+    parse(&mut if_node, parser, symbol_table) {
+        keyword_node.properties.insert("value".to_string(), "else if".to_string());
+        keyword_node.span = else_node.span.to(&if_node.span);
         return true;
     }
     return false;
 }
 
-pub fn parse_else_stmt(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
+pub fn parse_else_stmt(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
     //New scope made here
-    symbol_table.push_child(symbol_table.get_ordinal());
+    symbol_table.push_child();
     let current_table = &symbol_table.children.borrow()[symbol_table.children.borrow().len() - 1];
+    current_node.scope = Option::Some(current_table.clone());
 
-    if tokens[get_current_token_index()].val == "else".to_string() {
-        let mut keyword_node : Node = create_node(NodeType::Keyword);
-        let mut open_curly_node : Node = create_node(NodeType::Separator);
-        let mut body_node : Node = create_node(NodeType::Body);
-        let mut close_curly_node : Node = create_node(NodeType::Separator);
+    if parser.current_token().val == "else" {
+        let mut keyword_node : Node = create_expected_node(NodeType::Keyword, "else");
+        let mut block_node : Node = create_node(NodeType::Block);
 
         if 
-        parse(&mut keyword_node, tokens, current_table) &&
-        parse(&mut open_curly_node, tokens, current_table) &&
-        parse(&mut body_node, tokens, current_table) &&
-        parse(&mut close_curly_node, tokens, current_table) {
+        parse(&mut keyword_node, parser, current_table) &&
+        parse(&mut block_node, parser, current_table) {
 
 
             current_node.children.push(keyword_node);
-            current_node.children.push(open_curly_node);
-            current_node.children.push(body_node);
-            current_node.children.push(close_curly_node);
+            current_node.children.push(block_node);
 
             return true;
 
@@ -325,74 +445,112 @@ pub fn parse_else_stmt(current_node : &mut Node, tokens : &Vec<Token>, symbol_ta
     return true;
 }
 
-pub fn parse_while_stmt(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
+pub fn parse_while_stmt(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
     //New scope made here
     
-    symbol_table.push_child(symbol_table.get_ordinal());
+    symbol_table.push_child();
     let current_table = &symbol_table.children.borrow()[symbol_table.children.borrow().len() - 1];
+    current_node.scope = Option::Some(current_table.clone());
 
-    let mut keyword_node : Node = create_node(NodeType::Keyword);
-    let mut open_paren_node : Node = create_node(NodeType::Separator);
-    let mut cond_node : Node = create_node(NodeType::Condition_Expr);
-    let mut close_paren_node : Node = create_node(NodeType::Separator);
-    let mut open_curly_node : Node = create_node(NodeType::Separator);
-    let mut body_node : Node = create_node(NodeType::Body);
-    let mut close_curly_node : Node = create_node(NodeType::Separator);
+    let mut keyword_node : Node = create_expected_node(NodeType::Keyword, "while");
+    let mut open_paren_node : Node = create_expected_node(NodeType::Separator, "(");
+    let mut cond_node : Node = create_node(NodeType::Expression);
+    let mut close_paren_node : Node = create_expected_node(NodeType::Separator, ")");
+    let mut block_node : Node = create_node(NodeType::Block);
 
     if 
-    parse(&mut keyword_node, tokens, current_table) &&
-    parse(&mut open_paren_node, tokens, current_table) &&
-    parse(&mut cond_node, tokens, current_table) &&
-    parse(&mut close_paren_node, tokens, current_table) &&
-    parse(&mut open_curly_node, tokens, current_table) &&
-    parse(&mut body_node, tokens, current_table) &&
-    parse(&mut close_curly_node, tokens, current_table) {
+    parse(&mut keyword_node, parser, current_table) &&
+    parse(&mut open_paren_node, parser, current_table) &&
+    parse(&mut cond_node, parser, current_table) &&
+    parse(&mut close_paren_node, parser, current_table) &&
+    parse_loop_body(&mut block_node, parser, current_table) {
 
         current_node.children.push(keyword_node);
         current_node.children.push(open_paren_node);
         current_node.children.push(cond_node);
         current_node.children.push(close_paren_node);
+        current_node.children.push(block_node);
+
+        return true;
+    }
+
+
+    return false;
+}
+
+/* 
+The body gets its own scope, but the condition comes after it and is parsed in
+the enclosing scope, since the body's variables are gone by the time it runs.
+*/
+pub fn parse_do_while_stmt(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
+    symbol_table.push_child();
+    let current_table = &symbol_table.children.borrow()[symbol_table.children.borrow().len() - 1];
+    current_node.scope = Option::Some(current_table.clone());
+
+    let mut do_node : Node = create_expected_node(NodeType::Keyword, "do");
+    let mut open_curly_node : Node = create_expected_node(NodeType::Separator, "{");
+    let mut body_node : Node = create_node(NodeType::Body);
+    let mut close_curly_node : Node = create_expected_node(NodeType::Separator, "}");
+    let mut while_node : Node = create_expected_node(NodeType::Keyword, "while");
+    let mut open_paren_node : Node = create_expected_node(NodeType::Separator, "(");
+    let mut cond_node : Node = create_node(NodeType::Expression);
+    let mut close_paren_node : Node = create_expected_node(NodeType::Separator, ")");
+    let mut semicolon_node : Node = create_expected_node(NodeType::Separator, ";");
+
+    if
+    parse(&mut do_node, parser, current_table) &&
+    parse(&mut open_curly_node, parser, current_table) &&
+    parse_loop_body(&mut body_node, parser, current_table) &&
+    parse(&mut close_curly_node, parser, current_table) &&
+    parse(&mut while_node, parser, symbol_table) &&
+    parse(&mut open_paren_node, parser, symbol_table) &&
+    parse(&mut cond_node, parser, symbol_table) &&
+    parse(&mut close_paren_node, parser, symbol_table) &&
+    parse(&mut semicolon_node, parser, symbol_table) {
+
+        current_node.children.push(do_node);
         current_node.children.push(open_curly_node);
         current_node.children.push(body_node);
         current_node.children.push(close_curly_node);
+        current_node.children.push(while_node);
+        current_node.children.push(open_paren_node);
+        current_node.children.push(cond_node);
+        current_node.children.push(close_paren_node);
+        current_node.children.push(semicolon_node);
 
         return true;
     }
 
-
     return false;
 }
 
-pub fn parse_for_stmt(current_node : &mut Node, tokens : &Vec<Token>, symbol_table : &Rc<STNode>) -> bool {
+pub fn parse_for_stmt(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
 
-    symbol_table.push_child(symbol_table.get_ordinal());
+    symbol_table.push_child();
     let current_table = &symbol_table.children.borrow()[symbol_table.children.borrow().len() - 1];
+    current_node.scope = Option::Some(current_table.clone());
 
-    let mut keyword_node : Node = create_node(NodeType::Keyword);
-    let mut open_paren_node : Node = create_node(NodeType::Separator);
+    let mut keyword_node : Node = create_expected_node(NodeType::Keyword, "for");
+    let mut open_paren_node : Node = create_expected_node(NodeType::Separator, "(");
     let mut optional_1_node : Node = create_node(NodeType::Optional_Expr);
-    let mut semicolon_1_node : Node = create_node(NodeType::Separator);
+    let mut semicolon_1_node : Node = create_expected_node(NodeType::Separator, ";");
     let mut optional_2_node : Node = create_node(NodeType::Optional_Expr);
-    let mut semicolon_2_node : Node = create_node(NodeType::Separator);
+    let mut semicolon_2_node : Node = create_expected_node(NodeType::Separator, ";");
     let mut optional_3_node : Node = create_node(NodeType::Optional_Expr);
-    let mut close_paren_node : Node = create_node(NodeType::Separator);
-    let mut open_curly_node : Node = create_node(NodeType::Separator);
-    let mut body_node : Node = create_node(NodeType::Body);
-    let mut close_curly_node : Node = create_node(NodeType::Separator);
+    let mut close_paren_node : Node = create_expected_node(NodeType::Separator, ")");
+    let mut block_node : Node = create_node(NodeType::Block);
 
 
     if 
-    parse(&mut keyword_node, tokens, current_table) &&
-    parse(&mut open_paren_node, tokens, current_table) &&
-    parse(&mut optional_1_node, tokens, current_table) &&
-    parse(&mut semicolon_1_node, tokens, current_table) &&
-    parse(&mut optional_2_node, tokens, current_table) &&
-    parse(&mut semicolon_2_node, tokens, current_table) &&
-    parse(&mut optional_3_node, tokens, current_table) &&
-    parse(&mut close_paren_node, tokens, current_table) &&
-    parse(&mut open_curly_node, tokens, current_table) &&
-    parse(&mut body_node, tokens, current_table) &&
-    parse(&mut close_curly_node, tokens, current_table) {
+    parse(&mut keyword_node, parser, current_table) &&
+    parse(&mut open_paren_node, parser, current_table) &&
+    parse(&mut optional_1_node, parser, current_table) &&
+    parse(&mut semicolon_1_node, parser, current_table) &&
+    parse(&mut optional_2_node, parser, current_table) &&
+    parse(&mut semicolon_2_node, parser, current_table) &&
+    parse(&mut optional_3_node, parser, current_table) &&
+    parse(&mut close_paren_node, parser, current_table) &&
+    parse_loop_body(&mut block_node, parser, current_table) {
         
         current_node.children.push(keyword_node);
         current_node.children.push(open_paren_node);
@@ -402,12 +560,133 @@ pub fn parse_for_stmt(current_node : &mut Node, tokens : &Vec<Token>, symbol_tab
         current_node.children.push(semicolon_2_node);
         current_node.children.push(optional_3_node);
         current_node.children.push(close_paren_node);
+        current_node.children.push(block_node);
+
+        return true;
+    }
+
+    return false;
+}
+
+/* 
+The body of a switch gets a single scope that every case label shares, so a
+variable declared after one label is still visible after the labels below it.
+*/
+pub fn parse_switch_stmt(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
+    symbol_table.push_child();
+    let current_table = &symbol_table.children.borrow()[symbol_table.children.borrow().len() - 1];
+    current_node.scope = Option::Some(current_table.clone());
+
+    let mut keyword_node : Node = create_expected_node(NodeType::Keyword, "switch");
+    let mut open_paren_node : Node = create_expected_node(NodeType::Separator, "(");
+    let mut value_node : Node = create_node(NodeType::Expression);
+    let mut close_paren_node : Node = create_expected_node(NodeType::Separator, ")");
+    let mut open_curly_node : Node = create_expected_node(NodeType::Separator, "{");
+    let mut close_curly_node : Node = create_expected_node(NodeType::Separator, "}");
+
+    if
+    !parse(&mut keyword_node, parser, current_table) ||
+    !parse(&mut open_paren_node, parser, current_table) ||
+    !parse(&mut value_node, parser, current_table) ||
+    !parse(&mut close_paren_node, parser, current_table) ||
+    !parse(&mut open_curly_node, parser, current_table) {
+        return false;
+    }
+
+    let mut labels : Vec<Node> = Vec::new();
+    parser.switch_depth += 1;
+    let parsed : bool = parse_case_labels(&mut labels, parser, current_table);
+    parser.switch_depth -= 1;
+
+    if parsed && parse(&mut close_curly_node, parser, current_table) {
+        current_node.children.push(keyword_node);
+        current_node.children.push(open_paren_node);
+        current_node.children.push(value_node);
+        current_node.children.push(close_paren_node);
         current_node.children.push(open_curly_node);
-        current_node.children.push(body_node);
+        current_node.children.extend(labels);
         current_node.children.push(close_curly_node);
-
         return true;
     }
+    return false;
+}
+
+//Parses the labels of a switch body, each followed by the statements up to the next one
+fn parse_case_labels(labels : &mut Vec<Node>, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
+    let mut values : Vec<i32> = Vec::new();
+    let mut has_default : bool = false;
+
+    while parser.current_token().val != "}" && !parser.at_end() {
+        if !is_case_keyword(&parser.current_token().val) {
+            parser.expect("`case` or `default`".to_string());
+            return false;
+        }
+
+        let mut case_node : Node = create_node(NodeType::Case_Label);
+        if !parse(&mut case_node, parser, symbol_table) {
+            return false;
+        }
+
+        //The switch could not tell two labels for the same value apart
+        let label_span : Span = case_node.children[0].span.to(&case_node.children[case_node.children.len() - 2].span);
+        if let Option::Some(value) = case_node.constant {
+            if values.contains(&value) {
+                parser.report(Diagnostic::error("E0009", format!("duplicate case value `{}`", value), label_span, "this value already has a label".to_string()));
+            }
+            values.push(value);
+        }
+        else {
+            if has_default {
+                parser.report(Diagnostic::error("E0009", "multiple `default` labels in one switch".to_string(), label_span, "this switch already has a default label".to_string()));
+            }
+            has_default = true;
+        }
+        labels.push(case_node);
+    }
+    return true;
+}
 
+pub fn parse_case_label(current_node : &mut Node, parser : &mut Parser, symbol_table : &Rc<STNode>) -> bool {
+    let keyword : String = parser.current_token().val.clone();
+    let mut keyword_node : Node = create_expected_node(NodeType::Keyword, keyword.as_str());
+    let mut value_node : Node = create_node(NodeType::Expression);
+    let mut colon_node : Node = create_expected_node(NodeType::Separator, ":");
+    let mut body_node : Node = create_node(NodeType::Body);
+    body_node.properties.insert("case_body".to_string(), keyword.clone());
+
+    if !parse(&mut keyword_node, parser, symbol_table) {
+        return false;
+    }
+    current_node.children.push(keyword_node);
+
+    if keyword == "case" {
+        if !parse(&mut value_node, parser, symbol_table) {
+            return false;
+        }
+        //Negative constants are folded while parsing, so anything other than a constant node is not one
+        let value : &Node = &value_node.children[0];
+        match value.constant {
+            Option::Some(constant) if matches!(value.node_type, NodeType::Constant) => {
+                current_node.constant = Option::Some(constant);
+            }
+            _ => {
+                parser.report(Diagnostic::error("E0001", "expected a constant case value".to_string(), value.span, "not a constant".to_string()));
+                return false;
+            }
+        }
+        current_node.children.push(value_node);
+    }
+
+    if
+    parse(&mut colon_node, parser, symbol_table) &&
+    parse(&mut body_node, parser, symbol_table) {
+        current_node.children.push(colon_node);
+        current_node.children.push(body_node);
+        return true;
+    }
     return false;
 }
+
+fn is_case_keyword(input : &String) -> bool {
+    return input == "case" || input == "default";
+}
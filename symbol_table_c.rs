@@ -3,89 +3,89 @@ use std::collections::HashMap;
 use std::rc::{Rc, Weak};
 use std::cell::{RefCell, RefMut};
 
+use crate::token_c::Span;
+
+//Number of arguments the System V AMD64 calling convention passes in registers
+pub const REGISTER_ARGUMENTS : usize = 6;
+
 #[derive(Clone)]
 pub struct Symbol {
     pub primitive : String, 
     pub addr : i32,
-    pub size : u32,
-    pub register : i32,
     pub args : u32,
-    pub func : bool
+    pub func : bool,
+    //Primitives of a function's parameters, in order
+    pub params : Vec<String>,
+    //Whether a function's body has been seen yet, or only a prototype
+    pub defined : bool,
+    //Where the symbol was declared, so diagnostics can point back at it
+    pub span : Span
 }
 
 pub struct SymbolTable {
-    pub symbol_table : HashMap<String, Symbol>,
-    pub ordinal : i32
+    pub symbol_table : HashMap<String, Symbol>
 }
 
 
 impl SymbolTable {
-    /* Handles updating address for each local variable from base of stack frame
-    for easier assembly generation */
-    pub fn insert(&mut self, identifier : &String, prim : &String, args : u32, func : bool) {
+    /* Locals get their stack slots once the whole function is known, since slots
+    are shared between scopes that are never live at the same time */
+    pub fn insert(&mut self, identifier : &str, prim : &str, args : u32, func : bool, span : Span) {
         //Construct symbol
-        self.symbol_table.insert(identifier.clone(), Symbol{primitive : prim.clone(), addr : self.ordinal as i32 * -8, size : 8, register : -1, args : args, func : func});
-
-        //Update stack pointer
-        self.ordinal += 1;
+        self.symbol_table.insert(identifier.to_string(), Symbol{primitive : prim.to_string(), addr : 0, args, func, params : Vec::new(), defined : false, span});
     }
 
-    pub fn insert_argument(&mut self, identifier : &String, prim : &String, arg_ordinal : i32) {
-        self.symbol_table.insert(identifier.clone(), Symbol{primitive : prim.clone(), addr : arg_ordinal as i32 * 8, size : 8, register : -1, args : 0, func : false});
+    /* 
+    The first REGISTER_ARGUMENTS arguments arrive in registers, and the callee
+    copies them into slots of its own frame like any other local. The remaining
+    ones stay where the caller pushed them, just above the return address.
+    */
+    pub fn insert_argument(&mut self, identifier : &str, prim : &str, arg_ordinal : i32, span : Span) {
+        let mut addr : i32 = 0;
+        if arg_ordinal >= REGISTER_ARGUMENTS as i32 {
+            addr = 16 + (arg_ordinal - REGISTER_ARGUMENTS as i32) * 8;
+        }
+        self.symbol_table.insert(identifier.to_string(), Symbol{primitive : prim.to_string(), addr, args : 0, func : false, params : Vec::new(), defined : false, span});
     }
 
-    pub fn query(&self, identifier : &String) -> Option<&Symbol>{
-        return self.symbol_table.get(identifier)
+    //Functions live in the global scope, so unlike variables they do not take up a stack slot
+    pub fn insert_function(&mut self, identifier : &str, prim : &str, params : &[String], defined : bool, span : Span) {
+        self.symbol_table.insert(identifier.to_string(), Symbol{primitive : prim.to_string(), addr : 0, args : params.len() as u32, func : true, params : params.to_vec(), defined, span});
     }
 
-    pub fn modify_register(&mut self, identifier : &String, register : i32) {
-        self.symbol_table.insert(
-        identifier.clone(), 
-        Symbol {
-            primitive: self.symbol_table[identifier].primitive.clone(),
-            addr: self.symbol_table[identifier].addr,
-            size: self.symbol_table[identifier].size,
-            register: register,
-            args : self.symbol_table[identifier].args,
-            func : self.symbol_table[identifier].func
-        });
+    pub fn query(&self, identifier : &str) -> Option<&Symbol>{
+        return self.symbol_table.get(identifier)
     }
 
 }
 
 pub struct STNode {
-    pub scope_index : RefCell<usize>,
     pub table : RefCell<SymbolTable>,
     pub parent : Option<RefCell<Weak<STNode>>>,
     pub children : RefCell<Vec<Rc<STNode>>>
 }
 
 pub trait TreeMethods {
-    fn push_child(&self, ordinal : i32);
+    fn push_child(&self);
     
-    fn get_table(&self) -> RefMut<SymbolTable>;
-
-    fn scope_lookup(&self, identifier : &String) -> Option<Symbol>;
+    fn get_table(&self) -> RefMut<'_, SymbolTable>;
 
-    fn bind(&self, identifier : &String, prim : &String, args : u32, func : bool);
+    fn scope_lookup(&self, identifier : &str) -> Option<Symbol>;
 
-    fn bind_arg(&self, identifier : &String, prim : &String, arg_ordinal : i32);
+    fn bind(&self, identifier : &str, prim : &str, args : u32, func : bool, span : Span);
 
-    fn modify_register(&self, identifier : &String, register : i32);
+    fn bind_arg(&self, identifier : &str, prim : &str, arg_ordinal : i32, span : Span);
 
-    fn get_ordinal(&self) -> i32;
+    fn bind_function(&self, identifier : &str, prim : &str, params : &[String], defined : bool, span : Span);
 
-    fn in_table(&self, identifier : &String) -> bool;
 }
 
-pub fn create_new_stnode(ordinal : i32) -> Rc<STNode> {
+pub fn create_new_stnode() -> Rc<STNode> {
     let sym_tab: SymbolTable = SymbolTable {
-        symbol_table : HashMap::new(),
-        ordinal : ordinal
+        symbol_table : HashMap::new()
     };
 
     return Rc::new(STNode {
-        scope_index : RefCell::new(0),
         table : RefCell::new(sym_tab),
         parent : Option::Some(RefCell::new(Weak::new())),
         children : RefCell::new(Vec::new())
@@ -94,72 +94,48 @@ pub fn create_new_stnode(ordinal : i32) -> Rc<STNode> {
 
 impl TreeMethods for Rc<STNode> {
 
-    fn push_child(&self, ordinal : i32) {
+    fn push_child(&self) {
         
-        let child: Rc<STNode> = create_new_stnode(ordinal);
+        let child: Rc<STNode> = create_new_stnode();
 
-        *child.parent.as_ref().unwrap().borrow_mut() = Rc::downgrade(&self);
+        *child.parent.as_ref().unwrap().borrow_mut() = Rc::downgrade(self);
         self.children.borrow_mut().push(child);
     }
 
 
-    fn get_table(&self) -> RefMut<SymbolTable> {
+    fn get_table(&self) -> RefMut<'_, SymbolTable> {
         return self.table.borrow_mut();
     }
 
-    fn scope_lookup(&self, identifier : &String) -> Option<Symbol> {
+    fn scope_lookup(&self, identifier : &str) -> Option<Symbol> {
         
-        if let Option::Some(symbol) = self.table.borrow().query(&identifier) {
+        if let Option::Some(symbol) = self.table.borrow().query(identifier) {
             return Option::Some(symbol.clone());
         }
 
         let mut current_node: Rc<STNode> = self.clone();
 
-        while let Option::Some(parent_node) = &current_node.parent {
-            if let Option::Some(symbol) = parent_node.borrow().upgrade().unwrap().table.borrow().query(&identifier) {
+        //The global scope has an empty parent, so the walk stops once the parent can't be upgraded
+        while let Option::Some(parent_node) = current_node.parent.as_ref().and_then(|parent| parent.borrow().upgrade()) {
+            if let Option::Some(symbol) = parent_node.table.borrow().query(identifier) {
                 return Option::Some(symbol.clone());
             }
             
-            current_node = parent_node.clone().borrow().upgrade().unwrap();
+            current_node = parent_node;
         }
 
         return Option::None;
     }
 
-    fn bind(&self, identifier : &String, prim : &String, args : u32, func : bool) {
-        self.table.borrow_mut().insert(identifier, prim, args, func);
+    fn bind(&self, identifier : &str, prim : &str, args : u32, func : bool, span : Span) {
+        self.table.borrow_mut().insert(identifier, prim, args, func, span);
     }
 
-    fn bind_arg(&self, identifier : &String, prim : &String, arg_ordinal : i32) {
-        self.table.borrow_mut().insert_argument(identifier, prim, arg_ordinal);
-    }
-
-    fn modify_register(&self, identifier : &String, register : i32) {
-        if self.in_table(identifier) {
-            self.table.borrow_mut().modify_register(identifier, register);
-            return;
-        }
-
-        let mut current_node: Rc<STNode> = self.clone();
-
-        while let Option::Some(parent_node) = &current_node.parent {
-            if parent_node.borrow().upgrade().unwrap().in_table(identifier) {
-                parent_node.borrow().upgrade().unwrap().table.borrow_mut().modify_register(identifier, register);
-                return;
-            }
-            
-            current_node = parent_node.clone().borrow().upgrade().unwrap();
-        }
-    }
-
-    fn in_table(&self, identifier : &String) -> bool {
-        if let Option::Some(_symbol) = self.table.borrow().query(identifier) {
-            return true;
-        }
-        return false;
+    fn bind_arg(&self, identifier : &str, prim : &str, arg_ordinal : i32, span : Span) {
+        self.table.borrow_mut().insert_argument(identifier, prim, arg_ordinal, span);
     }
 
-    fn get_ordinal(&self) -> i32 {
-        return self.table.borrow().ordinal;
+    fn bind_function(&self, identifier : &str, prim : &str, params : &[String], defined : bool, span : Span) {
+        self.table.borrow_mut().insert_function(identifier, prim, params, defined, span);
     }
 }
@@ -0,0 +1,11 @@
+int main() {
+    int x = 0;
+    if(x == 0) {
+        break;
+    }
+    switch(x) {
+        case 0:
+            continue;
+    }
+    return x;
+}
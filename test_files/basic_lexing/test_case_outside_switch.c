@@ -0,0 +1,6 @@
+int main() {
+    int x = 2;
+    case 2:
+        x = 3;
+    return x;
+}
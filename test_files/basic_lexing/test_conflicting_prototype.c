@@ -0,0 +1,10 @@
+int add(int a);
+
+//The definition returns a different type than the prototype promised
+char add(int a) {
+    return a + 1;
+}
+
+int main() {
+    return add(1);
+}
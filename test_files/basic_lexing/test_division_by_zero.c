@@ -0,0 +1,6 @@
+int main() {
+    int x = 10;
+    int y = x / 0;
+    y %= 0;
+    return y;
+}
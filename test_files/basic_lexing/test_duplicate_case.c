@@ -0,0 +1,16 @@
+int main() {
+    int x = 2;
+    switch(x) {
+        case 1:
+            x = 3;
+        case -1:
+            x = 4;
+        case 1:
+            x = 5;
+        default:
+            x = 6;
+        default:
+            x = 7;
+    }
+    return x;
+}
@@ -0,0 +1,8 @@
+int f() {
+    return 1;
+}
+
+int main() {
+    int x = f;
+    return f;
+}
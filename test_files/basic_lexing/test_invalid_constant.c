@@ -0,0 +1,4 @@
+int main() {
+    int a = 3.5;
+    return a;
+}
@@ -0,0 +1,26 @@
+//Every mistake below is independent, so each one is reported once and nothing else is
+int helper(int a) {
+    int b = a * 2
+    int c = b + 1;
+    return c;
+}
+
+int broken( {
+    return 1;
+}
+
+int main() {
+    int x = 3;
+    x = x + missing;
+    if x > 2 {
+        x = 1;
+    }
+    int y = helper(x)
+    while (y > 0) {
+        y = y - 1;
+    }
+    for (int i = 0; i < 3 i++) {
+        x = x + i;
+    }
+    return x + y;
+}
@@ -0,0 +1,7 @@
+int g(int a) {
+    return a;
+}
+
+int main() {
+    return g(1,);
+}
@@ -0,0 +1,8 @@
+float half(float a) {
+    return a;
+}
+
+int main() {
+    float x = 1;
+    return x;
+}
@@ -0,0 +1,29 @@
+int set_flag(int flags, int bit) {
+    return flags | 1 << bit;
+}
+
+int has_flag(int flags, int bit) {
+    return flags >> bit & 1;
+}
+
+int main() {
+    int flags = 0;
+    flags = set_flag(flags, 3);
+    flags = set_flag(flags, 0);
+    flags = set_flag(flags, 5);
+
+    int mask = ~flags & 255;
+    int toggled = flags ^ 12;
+    int negative = -64 >> 2;
+    int shift = 2;
+    int field = 1234 >> shift << shift;
+
+    int result = has_flag(flags, 3) + has_flag(flags, 1) * 2;
+    if(negative == -16) {
+        result = result + 4;
+    }
+    if((mask & flags) == 0 && (flags | mask) == 255) {
+        result = result + 8;
+    }
+    return result + toggled + field % 256 + (1 << shift ^ 3);
+}
@@ -0,0 +1,52 @@
+int first_multiple(int n, int limit) {
+    for(int i = 1; ; i++) {
+        if(i * n > limit) {
+            return i * n;
+        }
+    }
+    return 0;
+}
+
+int main() {
+    int total = 0;
+
+    for(int i = 0; i < 10; i++) {
+        if(i % 2 == 0) {
+            continue;
+        }
+        if(i > 7) {
+            break;
+        }
+        total += i;
+    }
+
+    int j = 0;
+    while(true) {
+        j++;
+        if(j < 3) {
+            continue;
+        }
+        int k = 0;
+        while(k < 10) {
+            k++;
+            if(k == 4) {
+                break;
+            }
+            total += 100;
+        }
+        if(j == 5) {
+            break;
+        }
+    }
+
+    for(int x = 0; x < 3; x++) {
+        for(int y = 0; y < 3; y++) {
+            if(y == x) {
+                continue;
+            }
+            total += 1;
+        }
+    }
+
+    return total % 256 + first_multiple(7, 50);
+}
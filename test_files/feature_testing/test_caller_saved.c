@@ -0,0 +1,14 @@
+//Works through enough temporaries to use registers the caller may be holding values in
+int scramble(int a) {
+    return (a + 1) * (a + 2) - (a + 3) * (a - 4) + (a + 5) * (a - 6) - a * a;
+}
+
+int main() {
+    int a = 3;
+    int b = 5;
+
+    //a, and then the sum so far, are held in a register while each call runs
+    int r = a + scramble(b);
+    r = r * 2 + scramble(a) - scramble(scramble(b) - 40) + a * b;
+    return r + b * scramble(a - b);
+}
@@ -0,0 +1,18 @@
+//The seventh and later arguments are passed on the stack, the seventh closest to the return address
+int seventh(int a, int b, int c, int d, int e, int f, int g) {
+    return g * 10 + a;
+}
+
+//An odd number of stack arguments needs padding to keep the stack aligned at the call
+int ninth(int a, int b, int c, int d, int e, int f, int g, int h, int i) {
+    return i - h + g - f;
+}
+
+//Calls made from a function that was given stack arguments itself
+int forward(int a, int b, int c, int d, int e, int f, int g, int h) {
+    return seventh(h, g, f, e, d, c, b) + ninth(a, b, c, d, e, f, g, h, 1);
+}
+
+int main() {
+    return seventh(1, 2, 3, 4, 5, 6, 7) - ninth(1, 2, 3, 4, 5, 6, 9, 8, 30) + forward(1, 2, 3, 4, 5, 6, 7, 8);
+}
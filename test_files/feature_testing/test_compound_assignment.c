@@ -0,0 +1,45 @@
+int next(int x) {
+    return ++x;
+}
+
+int main() {
+    int total = 0;
+    for(int i = 0; i < 5; i++) {
+        total += i;
+    }
+
+    int count = 3;
+    while(count-- > 0) {
+        total *= 2;
+    }
+
+    int a = 10;
+    a -= 3;
+    a /= 2;
+    a %= 2;
+    a <<= 4;
+    a |= 5;
+    a ^= 1;
+    a &= 30;
+    a >>= 1;
+
+    int b = 5;
+    int c = b++;
+    c += ++b;
+    int d = next(b--);
+    d -= --b;
+    int e = 0;
+    int f = e = c += 2;
+
+    char small = 126;
+    small++;
+    small += 1;
+    bool flag = 0;
+    flag += 2;
+
+    for(int j = 10; j > 0; j -= 3) {
+        --total;
+    }
+
+    return total + a + b + c + d + e + f + small + flag;
+}
@@ -0,0 +1,26 @@
+int main() {
+    int a = 0 - 7;
+    int b = 2;
+    int c = 0 - 2;
+    int result = 0;
+
+    if(a / b == 0 - 3) {
+        result = result + 1;
+    }
+    if(a % b == 0 - 1) {
+        result = result + 2;
+    }
+    if(7 / c == 0 - 3) {
+        result = result + 4;
+    }
+    if(7 % c == 1) {
+        result = result + 8;
+    }
+    if(a % c == 0 - 1) {
+        result = result + 16;
+    }
+
+    int hours = 100 / 60;
+    int minutes = 100 % 60 * 2;
+    return result + hours + minutes;
+}
@@ -0,0 +1,32 @@
+int digits(int n) {
+    int count = 0;
+    do {
+        count++;
+        n /= 10;
+    } while(n != 0);
+    return count;
+}
+
+int main() {
+    int total = digits(0) + digits(12345) * 10;
+
+    int i = 100;
+    do {
+        total += 1;
+    } while(i < 10);
+
+    int j = 0;
+    do {
+        j++;
+        if(j % 3 == 0) {
+            continue;
+        }
+        if(j > 10) {
+            break;
+        }
+        int doubled = j * 2;
+        total += doubled;
+    } while(j < 20);
+
+    return total;
+}
@@ -0,0 +1,49 @@
+int sign(int x) {
+    if (x < 0) {
+        return -1;
+    }
+    else if (x == 0) {
+        return 0;
+    }
+    else {
+        return 1;
+    }
+}
+
+int grade(int score) {
+    if (score >= 90) return 4;
+    else if (score >= 80) return 3;
+    elif (score >= 70) return 2;
+    else if (score >= 60) return 1;
+    else return 0;
+}
+
+int sum_odd(int n) {
+    int total = 0;
+    for (int i = 0; i < n; i++)
+        if (i % 2 == 1)
+            total += i;
+    return total;
+}
+
+int main() {
+    int result = 0;
+
+    //The else belongs to the nearest if
+    if (result == 0)
+        if (result == 1)
+            result = 100;
+        else
+            result = 7;
+
+    result += sign(-5) + sign(0) * 10 + sign(12) * 3;
+    result += grade(95) * 10 + grade(85) + grade(75) + grade(65) + grade(10);
+    result += sum_odd(10);
+
+    int x = 10;
+    while (x > 0)
+        x--;
+    if (x == 0) result += 1; else result -= 1;
+
+    return result;
+}
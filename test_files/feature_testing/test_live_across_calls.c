@@ -0,0 +1,21 @@
+int square(int a) {
+    return a * a;
+}
+
+int pick(int a) {
+    if(a < 1) {
+        return 10;
+    }
+    elif(a < 2) {
+        return 20;
+    }
+    return 40;
+}
+
+int main() {
+    int total = 0;
+    for(int i = 0; i < 4; i = i + 1) {
+        total = total + square(i) + pick(i) * 2 - square(pick(i) - 9);
+    }
+    return total;
+}
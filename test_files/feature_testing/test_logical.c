@@ -0,0 +1,27 @@
+int main() {
+    int x = 0;
+    int y = 7;
+    int r = 0;
+
+    //The division is never evaluated, since x being 0 already makes the && false
+    if(x != 0 && 10 / x > 1) {
+        r = r + 1;
+    }
+
+    //The right side of || is skipped once the left side is true
+    if(y || y++) {
+        r = r + y;
+    }
+    if(x && y++) {
+        r = r + 100;
+    }
+    if(x || y++) {
+        r = r + y;
+    }
+
+    //Both operands count as true for any value other than 0
+    int a = 2 && 4;
+    int b = 0 || -3;
+    int c = !6 + !0;
+    return r * 10 + a + b + c;
+}
@@ -0,0 +1,22 @@
+int negate(int x) {
+    return -x;
+}
+
+int main() {
+    int a = -5;
+    int b = -a;
+    int c = +a;
+    int d = 3 - -2;
+    int e = -a * -4;
+    int f = negate(-7) + negate(b);
+    int g = - -9;
+    int h = -2147483648;
+
+    if(h < -2147483647) {
+        a = a + 100;
+    }
+    if(-a / 2 == 2) {
+        b = b + 10;
+    }
+    return a + b + c + d + e + f + g + 100;
+}
@@ -0,0 +1,29 @@
+int add(int a, int b) {
+    return a + b;
+}
+
+int main() {
+    int total = 0;
+
+    for(int i = 0; i < 4; i = i + 1) {
+        int square = i * i;
+        if(square > 3) {
+            int bonus = add(square, i);
+            total = total + bonus;
+        }
+        else {
+            int small = add(i, 1);
+            total = total + small;
+        }
+    }
+
+    int after = 10;
+    int j = 0;
+    while(j < 3) {
+        int step = add(j, after);
+        total = total + step;
+        j = j + 1;
+    }
+
+    return total + after;
+}
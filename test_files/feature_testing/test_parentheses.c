@@ -0,0 +1,20 @@
+int add(int a, int b) {
+    return a + b;
+}
+
+int main() {
+    int a = 3;
+    int b = 4;
+    int c = 5;
+
+    int sum = (a + b) * c;
+    int nested = ((a + (b * (c - 1))) - (a - b)) / ((2));
+    int grouped = a - (b - c);
+    int call = add((a + 1) * 2, (b)) * (c % (a + 1));
+    bool check = !(a > b) && (b < c || (a == 0));
+
+    if((sum > 30) && !(nested < 0)) {
+        sum = sum + 1;
+    }
+    return sum + nested + grouped + call + check + -(a - b);
+}
@@ -0,0 +1,28 @@
+int add(int a) {
+    return a + 1;
+}
+int main() {
+    int x = 3;
+    int y = 4;
+    bool c = true;
+    bool t = x + 1 < y * 2;
+    bool u = x < y == c;
+    int r = 0;
+    if(t && u || !c) {
+        r = add(x * 2 + y);
+    }
+    if(x == 3) {
+        r = r + 10;
+    }
+    //Comparisons give an int, and every value other than 0 counts as true
+    int a = 5;
+    int b = 9;
+    int one = 1;
+    if((a < b) == one && !(a > b) == one) {
+        r = r + 20;
+    }
+    if(!a || !(b - 9) && a && b) {
+        r = r + 40;
+    }
+    return r - 2 * 3 + 12 / 4 - 1;
+}
@@ -0,0 +1,18 @@
+int twice(int a);
+bool is_small(int a);
+
+int main() {
+    int x = twice(4);
+    if(is_small(x)) {
+        return x;
+    }
+    return 0;
+}
+
+int twice(int a) {
+    return a * 2;
+}
+
+bool is_small(int a) {
+    return a < 10;
+}
@@ -0,0 +1,42 @@
+bool is_even(int n) {
+    if(n == 0) {
+        return true;
+    }
+    return is_odd(n - 1);
+}
+
+bool is_odd(int n) {
+    if(n == 0) {
+        return false;
+    }
+    return is_even(n - 1);
+}
+
+int factorial(int n) {
+    if(n <= 1) {
+        return 1;
+    }
+    int rest = factorial(n - 1);
+    return n * rest;
+}
+
+int fibonacci(int n) {
+    if(n < 2) {
+        return n;
+    }
+    int a = fibonacci(n - 1);
+    int b = fibonacci(n - 2);
+    return a + b;
+}
+
+int main() {
+    int f = factorial(5);
+    int g = fibonacci(10);
+    bool even = is_even(10);
+    bool odd = is_odd(7);
+    int result = f - 100 + g - 50;
+    if(even && odd) {
+        result = result + 1;
+    }
+    return result;
+}
@@ -0,0 +1,22 @@
+int main() {
+    int a = 1;
+    int b = 2;
+    int c = 3;
+    int d = 4;
+    int e = 5;
+    int f = 6;
+    int g = 7;
+    int h = 8;
+    int i = 9;
+    int j = 10;
+    int k = 11;
+    int l = 12;
+    int m = 13;
+    int n = 14;
+    int o = 15;
+    int p = 16;
+
+    //Every left operand stays live until the rest of the expression is computed, which takes more registers than there are
+    int x = a + (b * (c - (d + (e * (f - (g + (h * (i - (j + (k * (l - (m + (n * (o - p))))))))))))));
+    return x;
+}
@@ -0,0 +1,64 @@
+int classify(int x) {
+    int r = 0;
+    switch (x) {
+        case 0:
+            r = 10;
+            break;
+        case 1:
+        case 2:
+            r = 20;
+        case 3:
+            r = r + 5;
+            break;
+        case 5:
+            return 77;
+        case -1:
+            r = 3;
+            break;
+        default:
+            r = 1;
+    }
+    return r;
+}
+
+int sparse(int x) {
+    switch (x) {
+        case 1000: return 1;
+        case -50: return 2;
+        case 7: return 3;
+        case 99999: return 4;
+        case 12: return 5;
+        case 300: return 6;
+    }
+    return 0;
+}
+
+int loopy(int n) {
+    int total = 0;
+    for (int i = 0; i < n; i++) {
+        switch (i % 4) {
+            case 0:
+                continue;
+            case 1:
+                total += 1;
+                break;
+            default:
+                total += 10;
+        }
+        total += 100;
+    }
+    return total;
+}
+
+int main() {
+    int s = 0;
+    for (int i = -3; i < 8; i++) {
+        s = s * 3 + classify(i);
+        s = s % 100003;
+    }
+    int t = sparse(1000) + sparse(-50) * 10 + sparse(7) * 100 + sparse(99999) * 1000 + sparse(12) * 10000 + sparse(300) * 100000 + sparse(8) + sparse(-51);
+    char c = 3;
+    switch (c) { case 3: s += 1; }
+    switch (s) {}
+    return (s + t + loopy(10)) % 256;
+}
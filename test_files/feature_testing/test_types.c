@@ -0,0 +1,45 @@
+char shift(char c, int by) {
+    return c + by;
+}
+
+bool positive(int x) {
+    return x;
+}
+
+int main() {
+    int big = 2147483647;
+    int wrapped = big + 1;
+    int squared = 65536;
+    squared = squared * squared;
+
+    char c = 120;
+    c = c + 10;
+    char small = 0 - 200;
+
+    bool flag = 7;
+    bool other = flag + 1;
+
+    int result = 0;
+    if(wrapped < 0) {
+        result = result + 1;
+    }
+    if(c == 0 - 126) {
+        result = result + 2;
+    }
+    if(flag == 1) {
+        result = result + 4;
+    }
+    if(shift(100, 100) == 0 - 56) {
+        result = result + 8;
+    }
+    if(positive(0 - 3) == 1) {
+        result = result + 16;
+    }
+    if(squared == 0) {
+        result = result + 32;
+    }
+    if(other == 1) {
+        result = result + 64;
+    }
+    return result + small;
+}
@@ -22,16 +22,54 @@ pub enum TokenType {
     Default
 }
 
+/* 
+A Span records where a piece of source text came from. Every token gets one
+from the lexer, and the parser carries them onto the nodes it creates.
+Lines and columns start at 1, the offset is in bytes from the start of the file.
+*/
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Span {
+    pub file_id : u32,
+    pub offset : usize,
+    pub line : u32,
+    pub column : u32,
+    pub length : u32
+}
+
+impl Span {
+    //Creates the smallest span that covers both spans
+    pub fn to(&self, end : &Span) -> Span {
+        if end.offset + end.length as usize <= self.offset {
+            return *self;
+        }
+        return Span {
+            file_id : self.file_id,
+            offset : self.offset,
+            line : self.line,
+            column : self.column,
+            length : (end.offset + end.length as usize - self.offset) as u32
+        };
+    }
+}
+
 pub struct Token {
     pub token_type : TokenType,
-    pub val : String
+    pub val : String,
+    pub span : Span
 }
 
-pub fn is_whitespace(input : &String) -> bool {
+pub fn is_whitespace(input : &str) -> bool {
     let whitespace_regex : Regex = Regex::new(r"\s+").unwrap();
     return whitespace_regex.is_match(input);
 }
 
+//Operators made of several characters, longest first so `<<=` is not read as `<<` followed by `=`
+const MULTI_CHAR_OPERATORS : [&str; 20] = [
+    "<<=", ">>=",
+    "==", "!=", "&&", "||", "<=", ">=", "<<", ">>",
+    "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "++", "--"
+];
+
 pub fn is_operator(input : &String) -> bool {
     if
     input == "!" ||
@@ -49,7 +87,24 @@ pub fn is_operator(input : &String) -> bool {
     input == "-="||
     input == "=" ||
     input == "*" ||
-    input == "/" {
+    input == "/" ||
+    input == "%" ||
+    input == "&" ||
+    input == "|" ||
+    input == "^" ||
+    input == "~" ||
+    input == "<<" ||
+    input == ">>" ||
+    input == "*=" ||
+    input == "/=" ||
+    input == "%=" ||
+    input == "&=" ||
+    input == "|=" ||
+    input == "^=" ||
+    input == "<<=" ||
+    input == ">>=" ||
+    input == "++" ||
+    input == "--" {
         return true;
     }
     return false;
@@ -89,7 +144,7 @@ pub fn is_operator(input : &String) -> bool {
 // }
 
 pub fn is_identifier(input : &String) -> bool {
-    return input.chars().nth(0).unwrap().is_alphabetic() && !is_keyword(input) && !is_primitive(input);
+    return input.chars().next().unwrap().is_alphabetic() && !is_keyword(input) && !is_primitive(input);
 }
 
 pub fn is_separator(input : &String) -> bool {
@@ -99,6 +154,7 @@ pub fn is_separator(input : &String) -> bool {
     input == "{" || 
     input == "}" || 
     input == ";" ||
+    input == ":" ||
     input == "," {
         return true;
     }
@@ -111,8 +167,14 @@ fn is_keyword(input : &String) -> bool {
     input == "elif" ||
     input == "else" ||
     input == "while" ||
+    input == "do" ||
     input == "for" ||
+    input == "switch" ||
+    input == "case" ||
+    input == "default" ||
     input == "return" ||
+    input == "break" ||
+    input == "continue" ||
     input == "true" ||
     input == "false" {
         return true;
@@ -120,7 +182,7 @@ fn is_keyword(input : &String) -> bool {
     return false;
 }
 
-pub fn is_primitive(input : &String) -> bool {
+pub fn is_primitive(input : &str) -> bool {
     if 
     input == "int" ||
     input == "float" ||
@@ -131,40 +193,77 @@ pub fn is_primitive(input : &String) -> bool {
     return false;
 }
 
-pub fn construct_token(input : &String)-> Token{
+pub fn construct_token(input : &String, span : Span)-> Token{
     let mut t_type : TokenType = TokenType::Default;
     
     let identifier_regex : Regex = Regex::new(r"[[:alpha:]]+").unwrap();
     let integer_regex : Regex = Regex::new(r"[0-9]+").unwrap();
 
-    if is_separator(&input){
+    if is_separator(input){
         t_type = TokenType::Separator;
     }
-    else if is_keyword(&input)
+    else if is_keyword(input)
     {
         t_type = TokenType::Keyword;
     }
-    else if is_primitive(&input) {
+    else if is_primitive(input) {
         t_type = TokenType::Primitive;
     }
-    else if is_operator(&input){
+    else if is_operator(input){
         t_type = TokenType::Operator;
     }
-    else if identifier_regex.is_match(&input) {
+    else if identifier_regex.is_match(input) {
         t_type = TokenType::Identifier;
     }
-    else if integer_regex.is_match(&input) {
+    else if integer_regex.is_match(input) {
         t_type = TokenType::Constant;
     }
 
-    return Token{token_type : t_type, val : input.clone()};
+    return Token{token_type : t_type, val : input.clone(), span};
 }
 
 
-pub fn lex_file(input : String) -> Vec<Token> {
+/* 
+Keeps track of the line and column of every byte offset in the file being lexed
+so spans can be created for tokens.
+*/
+struct SourcePosition {
+    file_id : u32,
+    line_starts : Vec<usize>
+}
+
+impl SourcePosition {
+    fn new(input : &str, file_id : u32) -> SourcePosition {
+        let mut line_starts : Vec<usize> = vec![0];
+        for (offset, character) in input.char_indices() {
+            if character == '\n' {
+                line_starts.push(offset + 1);
+            }
+        }
+        return SourcePosition{file_id, line_starts};
+    }
+
+    fn span(&self, offset : usize, val : &str) -> Span {
+        //Index of the last line that starts at or before the offset
+        let line : usize = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        return Span {
+            file_id : self.file_id,
+            offset,
+            line : line as u32 + 1,
+            column : (offset - self.line_starts[line]) as u32 + 1,
+            length : val.len() as u32
+        };
+    }
+}
+
+pub fn lex_file(input : String, file_id : u32) -> Vec<Token> {
     let mut token_vector : Vec<Token> = Vec::new();
     
     let mut current_token_val : String = "".to_string();
+    //Byte offset of the first character of current_token_val
+    let mut current_token_start : usize = 0;
+
+    let position : SourcePosition = SourcePosition::new(&input, file_id);
 
 
     /* 
@@ -172,9 +271,13 @@ pub fn lex_file(input : String) -> Vec<Token> {
     delimit tokens according to whitespace, separators, or operators.
     Separators and operators will also be tokenized.
     */
-    let mut it =input.chars().peekable();
+    let mut it =input.char_indices().peekable();
     let mut comment : bool = false;
-    while let Option::Some(character) = it.next() {
+    while let Option::Some((offset, character)) = it.next() {
+        let next_character : String = match it.peek() {
+            Option::Some((_, next)) => next.to_string(),
+            Option::None => "".to_string()
+        };
         if comment {
             if character.to_string() == "\n" {
                 comment = false;
@@ -182,8 +285,8 @@ pub fn lex_file(input : String) -> Vec<Token> {
             continue;
         }
         if is_whitespace(&character.to_string()) {
-            if current_token_val != "".to_string() {
-                token_vector.push(construct_token(&current_token_val));
+            if !current_token_val.is_empty() {
+                token_vector.push(construct_token(&current_token_val, position.span(current_token_start, &current_token_val)));
             }
             current_token_val = "".to_string();
             continue;
@@ -191,57 +294,57 @@ pub fn lex_file(input : String) -> Vec<Token> {
         //Separators will flush the currently accumulated token
         if is_separator(&character.to_string()){
             
-            if current_token_val != "".to_string() {
-                token_vector.push(construct_token(&current_token_val));
+            if !current_token_val.is_empty() {
+                token_vector.push(construct_token(&current_token_val, position.span(current_token_start, &current_token_val)));
             }
-            token_vector.push(construct_token(&character.to_string()));
+            token_vector.push(construct_token(&character.to_string(), position.span(offset, &character.to_string())));
             current_token_val = "".to_string();
             continue;
         }
 
-        if 
-        (character.to_string() == "=" && it.peek().unwrap().to_string() == "=") ||
-        (character.to_string() == "!" && it.peek().unwrap().to_string() == "=") ||
-        (character.to_string() == "&" && it.peek().unwrap().to_string() == "&") ||
-        (character.to_string() == "|" && it.peek().unwrap().to_string() == "|") ||
-        (character.to_string() == "<" && it.peek().unwrap().to_string() == "=") ||
-        (character.to_string() == ">" && it.peek().unwrap().to_string() == "=") {
-            if current_token_val != "".to_string() {
-                token_vector.push(construct_token(&current_token_val));
+        if let Option::Some(operator) = MULTI_CHAR_OPERATORS.iter().find(|operator| input[offset..].starts_with(**operator)) {
+            if !current_token_val.is_empty() {
+                token_vector.push(construct_token(&current_token_val, position.span(current_token_start, &current_token_val)));
+            }
+            for _ in 1..operator.len() {
+                it.next();
             }
-            current_token_val.push(character);
-            current_token_val.push(it.next().unwrap());
-            token_vector.push(construct_token(&current_token_val));
+            token_vector.push(construct_token(&operator.to_string(), position.span(offset, operator)));
             current_token_val = "".to_string();
             continue;
         }
         
         if is_operator(&character.to_string()) {
-            if character.to_string() == "/" && it.peek().unwrap().to_string() == "/" {
-                if current_token_val != "".to_string() {
-                    token_vector.push(construct_token(&current_token_val));
+            if character.to_string() == "/" && next_character == "/" {
+                if !current_token_val.is_empty() {
+                    token_vector.push(construct_token(&current_token_val, position.span(current_token_start, &current_token_val)));
                 }
-                current_token_val.push(character);
-                current_token_val.push(it.next().unwrap());
-                // token_vector.push(construct_token(&current_token_val));
+                it.next();
                 current_token_val = "".to_string();
                 comment = true;
                 continue;
             }
 
             else {
-                if current_token_val != "".to_string() {
-                    token_vector.push(construct_token(&current_token_val));
+                if !current_token_val.is_empty() {
+                    token_vector.push(construct_token(&current_token_val, position.span(current_token_start, &current_token_val)));
                 }
-                token_vector.push(construct_token(&character.to_string()));
+                token_vector.push(construct_token(&character.to_string(), position.span(offset, &character.to_string())));
                 current_token_val = "".to_string();
                 continue;
             }
             
         }
+        if current_token_val.is_empty() {
+            current_token_start = offset;
+        }
         current_token_val.push(character);
     }
 
+    if !current_token_val.is_empty() {
+        token_vector.push(construct_token(&current_token_val, position.span(current_token_start, &current_token_val)));
+    }
+
 
     return token_vector;
 }